@@ -0,0 +1,212 @@
+//! The letters a game is played with and how they are written.
+//!
+//! A letter is written as a token of one or more characters, so that
+//! accented letters such as `Ñ` and digraph tiles such as the Spanish `CH`
+//! or the Catalan `L·L` are single tiles. Text is split into tokens from
+//! left to right, always taking the longest token that matches: with a `CH`
+//! tile, `CHICO` is read as `CH`, `I`, `C`, `O`. A token in square brackets
+//! is read as that tile alone, so that a `C` followed by an `H` can be
+//! written `[C]H`.
+
+use crate::error::Error;
+use crate::letter::{Blank, Letter, LetterSet, BLANK};
+
+/// The largest number of letters in an alphabet, as [`LetterSet`] keeps
+/// bit 0 for the GADDAG separator and compiled lexicons keep bit 63 for the
+/// terminal flag.
+pub const MAX_LETTERS: usize = 62;
+
+/// Characters with a meaning of their own in racks, boards and move
+/// notation, which a token may not contain.
+const RESERVED: &[char] = &['?', '.', '(', ')', '[', ']', '#', ':'];
+
+/// The tokens of the letters of a game, letter `n` being written as the
+/// `n`-th token.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alphabet {
+    tokens: Vec<String>,
+    /// The tokens written for blanks.
+    lowercase: Vec<String>,
+}
+
+impl Alphabet {
+    /// Builds an alphabet from the tokens of its letters in order. Tokens
+    /// must be uppercase, have a lowercase form for blanks and be distinct.
+    pub fn new<S: AsRef<str>>(tokens: &[S]) -> Result<Alphabet, String> {
+        if tokens.is_empty() || tokens.len() > MAX_LETTERS {
+            return Err(format!(
+                "an alphabet holds 1 to {MAX_LETTERS} letters, not {}",
+                tokens.len()
+            ));
+        }
+        let tokens: Vec<String> = tokens
+            .iter()
+            .map(|token| token.as_ref().to_string())
+            .collect();
+        for (index, token) in tokens.iter().enumerate() {
+            if token.is_empty()
+                || token.contains(char::is_whitespace)
+                || token.contains(RESERVED)
+                || token.to_uppercase() != *token
+                || token.to_lowercase() == *token
+            {
+                return Err(format!("'{token}' is not an uppercase letter"));
+            }
+            if tokens[..index].contains(token) {
+                return Err(format!("'{token}' is given twice"));
+            }
+        }
+        let lowercase = tokens.iter().map(|token| token.to_lowercase()).collect();
+        Ok(Alphabet { tokens, lowercase })
+    }
+
+    /// A to Z, the alphabet of the French and English editions.
+    pub fn latin() -> Alphabet {
+        Alphabet::new(&LATIN).unwrap()
+    }
+
+    /// The Spanish alphabet, with its CH, LL, Ñ and RR tiles and without K
+    /// and W.
+    pub fn spanish() -> Alphabet {
+        Alphabet::new(&[
+            "A", "B", "C", "CH", "D", "E", "F", "G", "H", "I", "J", "L", "LL", "M", "N", "Ñ", "O",
+            "P", "Q", "R", "RR", "S", "T", "U", "V", "X", "Y", "Z",
+        ])
+        .unwrap()
+    }
+
+    /// A to Z followed by Ä, Ö and Ü.
+    pub fn german() -> Alphabet {
+        let mut tokens = LATIN.to_vec();
+        tokens.extend(["Ä", "Ö", "Ü"]);
+        Alphabet::new(&tokens).unwrap()
+    }
+
+    /// The number of letters, which are numbered from 1.
+    pub fn letter_count(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn letters(&self) -> impl Iterator<Item = Letter> {
+        1..=self.tokens.len() as Letter
+    }
+
+    /// The set of every letter of the alphabet.
+    pub fn all_letters(&self) -> LetterSet {
+        self.letters().fold(0, |set, letter| set | 1 << letter)
+    }
+
+    /// The tokens of the letters in order.
+    pub fn tokens(&self) -> impl Iterator<Item = &str> {
+        self.tokens.iter().map(String::as_str)
+    }
+
+    /// Writes a tile: its token, in lowercase for a blank standing for a
+    /// letter, `?` for an unassigned blank and a space for an empty square.
+    pub fn token(&self, letter: Letter) -> &str {
+        match letter {
+            0 => " ",
+            BLANK => "?",
+            _ if letter.is_blank() => &self.lowercase[letter.unblanked() as usize - 1],
+            // Letters only come out of `read_tile`, so an index past the
+            // alphabet is never reached unless one is built by hand.
+            _ => self
+                .tokens
+                .get(letter as usize - 1)
+                .map_or("\u{fffd}", String::as_str),
+        }
+    }
+
+    /// Reads the tile at the start of `text`, returning it with the number
+    /// of bytes it takes, or `None` if `text` does not start with a tile.
+    pub fn read_tile(&self, text: &str) -> Option<(Letter, usize)> {
+        if text.starts_with('?') {
+            return Some((BLANK, 1));
+        }
+        if let Some(bracketed) = text.strip_prefix('[') {
+            if bracketed.starts_with('[') {
+                return None;
+            }
+            let (letter, length) = self.read_tile(bracketed)?;
+            return match bracketed[length..].starts_with(']') && letter != BLANK {
+                true => Some((letter, length + 2)),
+                false => None,
+            };
+        }
+        let mut longest: Option<(Letter, usize)> = None;
+        for (index, (token, lowercase)) in self.tokens.iter().zip(&self.lowercase).enumerate() {
+            let letter = index as Letter + 1;
+            for (candidate, tile) in [(token, letter), (lowercase, letter | BLANK)] {
+                if text.starts_with(candidate.as_str())
+                    && longest.is_none_or(|(_, length)| candidate.len() > length)
+                {
+                    longest = Some((tile, candidate.len()));
+                }
+            }
+        }
+        longest
+    }
+
+    /// Splits `text` into tiles, lowercase standing for blanks and `?` for
+    /// an unassigned blank.
+    pub fn parse(&self, text: &str) -> Result<Vec<Letter>, Error> {
+        let mut letters = Vec::new();
+        let mut rest = text;
+        while let Some(character) = rest.chars().next() {
+            let (letter, length) = self
+                .read_tile(rest)
+                .ok_or(Error::InvalidCharacter(character))?;
+            letters.push(letter);
+            rest = &rest[length..];
+        }
+        Ok(letters)
+    }
+
+    /// Writes tiles as read back by [`Alphabet::parse`]. A token that
+    /// would be read together with the next ones as a longer token, such as
+    /// the Spanish `L` before another `L`, is written in square brackets.
+    pub fn write(&self, letters: &[Letter]) -> String {
+        self.write_tiles(letters).concat()
+    }
+
+    /// Writes each tile as in [`Alphabet::write`], for text that puts
+    /// something of its own between some of them.
+    pub fn write_tiles(&self, letters: &[Letter]) -> Vec<String> {
+        let mut tiles: Vec<String> = letters
+            .iter()
+            .map(|letter| self.token(*letter).to_string())
+            .collect();
+        if self
+            .tokens
+            .iter()
+            .chain(&self.lowercase)
+            .all(|token| token.chars().count() == 1)
+        {
+            return tiles;
+        }
+        // Tokens are read from left to right, each depending only on the
+        // text after it, so they are written from the last one.
+        let mut after = String::new();
+        for (letter, tile) in letters.iter().zip(&mut tiles).rev() {
+            let text = format!("{tile}{after}");
+            if let Some((read, length)) = self.read_tile(&text) {
+                if read != *letter || length != tile.len() {
+                    *tile = format!("[{tile}]");
+                }
+            }
+            after = format!("{tile}{after}");
+        }
+        tiles
+    }
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Alphabet::latin()
+    }
+}
+
+const LATIN: [&str; 26] = [
+    "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R", "S",
+    "T", "U", "V", "W", "X", "Y", "Z",
+];
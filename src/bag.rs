@@ -0,0 +1,157 @@
+//! The tiles the player to move has not seen: those left in the bag and on
+//! the opponent's rack, worked out from the tile distribution of the ruleset
+//! minus the tiles on the board and on the player's rack.
+
+use crate::alphabet::Alphabet;
+use crate::board::Board;
+use crate::error::Error;
+use crate::letter::{Blank, Letter, BLANK};
+use crate::position::Position;
+use crate::ruleset::{tile_index, Ruleset};
+
+/// How many tiles of each letter are still unseen, blanks counted together
+/// whatever letter they stand for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnseenTiles {
+    /// Counts indexed like the tile table of the ruleset, blanks first.
+    counts: Vec<u8>,
+    rack_size: usize,
+}
+
+impl UnseenTiles {
+    /// Every tile of a full bag, before any is drawn.
+    pub fn full(ruleset: &Ruleset) -> UnseenTiles {
+        let mut counts = vec![ruleset.tile_count(BLANK)];
+        counts.extend(
+            ruleset
+                .alphabet
+                .letters()
+                .map(|letter| ruleset.tile_count(letter)),
+        );
+        UnseenTiles {
+            counts,
+            rack_size: ruleset.rack_size,
+        }
+    }
+
+    /// The tiles neither on `board` nor on `rack`. Fails if they hold more
+    /// tiles of a letter than the ruleset has, which no game can reach.
+    pub fn new(board: &Board, rack: &[Letter]) -> Result<UnseenTiles, Error> {
+        let ruleset = board.ruleset();
+        let mut unseen = UnseenTiles::full(ruleset);
+        let on_board = board.primary.cells().copied().filter(|tile| *tile != 0);
+        for tile in on_board.chain(rack.iter().copied()) {
+            if !unseen.remove(tile) {
+                return Err(too_many(ruleset, tile, board, rack));
+            }
+        }
+        Ok(unseen)
+    }
+
+    /// The tiles unseen from `position`, its rack being known or not. Also
+    /// fails if the bag it records holds tiles that cannot be unseen or,
+    /// the rack being known, not as many tiles as [`UnseenTiles::in_bag`].
+    pub fn for_position(position: &Position) -> Result<UnseenTiles, Error> {
+        let alphabet = &position.board.ruleset().alphabet;
+        let rack = match &position.rack {
+            Some(rack) => alphabet.parse(rack)?,
+            None => Vec::new(),
+        };
+        let unseen = UnseenTiles::new(&position.board, &rack)?;
+        if let Some(bag) = &position.bag {
+            let bag = alphabet.parse(bag)?;
+            let mut left = unseen.clone();
+            for &tile in &bag {
+                if !left.remove(tile) {
+                    let available = position.board.ruleset().tile_count(tile);
+                    let in_bag = bag.iter().filter(|other| **other == tile).count();
+                    return Err(Error::TooManyTiles {
+                        tile: alphabet.token(tile).to_string(),
+                        count: (available - unseen.count(tile)) as usize + in_bag,
+                        available,
+                    });
+                }
+            }
+            if position.rack.is_some() && bag.len() != unseen.in_bag() {
+                return Err(Error::WrongBagSize {
+                    recorded: bag.len(),
+                    expected: unseen.in_bag(),
+                });
+            }
+        }
+        Ok(unseen)
+    }
+
+    /// The number of unseen tiles of a letter, [`BLANK`] counting the blanks.
+    pub fn count(&self, tile: Letter) -> u8 {
+        self.counts.get(tile_index(tile)).copied().unwrap_or(0)
+    }
+
+    /// The number of unseen tiles.
+    pub fn total(&self) -> usize {
+        self.counts.iter().map(|count| *count as usize).sum()
+    }
+
+    /// The number of tiles still in the bag, the others being on the
+    /// opponent's rack.
+    pub fn in_bag(&self) -> usize {
+        self.total().saturating_sub(self.rack_size)
+    }
+
+    /// Takes a tile out of the unseen tiles, returning `false` if none is
+    /// left. Played blanks are taken as [`BLANK`].
+    pub fn remove(&mut self, tile: Letter) -> bool {
+        match self.counts.get_mut(tile_index(tile)) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Puts a tile back among the unseen tiles.
+    pub fn add(&mut self, tile: Letter) {
+        if let Some(count) = self.counts.get_mut(tile_index(tile)) {
+            *count += 1;
+        }
+    }
+
+    /// The letters with at least one unseen tile and their counts, letters
+    /// in alphabetical order and blanks last.
+    pub fn counts(&self) -> impl Iterator<Item = (Letter, u8)> + '_ {
+        (1..self.counts.len())
+            .map(|index| (index as Letter, self.counts[index]))
+            .chain([(BLANK, self.counts[0])])
+            .filter(|(_, count)| *count > 0)
+    }
+
+    /// Every unseen tile, one entry per tile, in the order of
+    /// [`UnseenTiles::counts`].
+    pub fn tiles(&self) -> Vec<Letter> {
+        self.counts()
+            .flat_map(|(tile, count)| std::iter::repeat_n(tile, count as usize))
+            .collect()
+    }
+
+    /// Writes the unseen tiles, such as `AAB?`.
+    pub fn write(&self, alphabet: &Alphabet) -> String {
+        alphabet.write(&self.tiles())
+    }
+}
+
+/// The error for a tile of `board` or `rack` that is not left.
+fn too_many(ruleset: &Ruleset, tile: Letter, board: &Board, rack: &[Letter]) -> Error {
+    let tile = if tile.is_blank() { BLANK } else { tile };
+    let count = board
+        .primary
+        .cells()
+        .chain(rack)
+        .filter(|other| **other != 0 && ((other.is_blank() && tile == BLANK) || **other == tile))
+        .count();
+    Error::TooManyTiles {
+        tile: ruleset.alphabet.token(tile).to_string(),
+        count,
+        available: ruleset.tile_count(tile),
+    }
+}
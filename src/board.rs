@@ -1,70 +1,400 @@
-use crate::letter::{FromChar, Letter, ToChar};
+//! The board and the placement of words on it.
+
+use crate::error::{Error, PlacementError};
+use crate::grid::Grid;
+use crate::letter::{Blank, Letter, BLANK};
+use crate::lexicon::Lexicon;
+use crate::position::Position;
+use crate::ruleset::Ruleset;
+use crate::score::{score_move, ScoreBreakdown, SquareType};
 use std::fmt::Display;
-use crate::score::PREMIUM_SQUARES;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// A placement that passed [`Board::validate_move`].
+pub struct Placement {
+    /// Every word formed, the main word first.
+    pub words: Vec<String>,
+    /// The tiles taken from the rack, blanks as [`BLANK`] whatever letter
+    /// they stand for.
+    pub rack_tiles: Vec<Letter>,
+    pub score: u16,
+    /// How `score` adds up, word by word.
+    pub breakdown: ScoreBreakdown,
+    /// The words formed that are not in the lexicon, which only
+    /// [`Board::check_move`] lets through.
+    pub phonies: Vec<String>,
+}
 
+/// The tiles on the board and the ruleset they are played under. `primary`
+/// holds the rows and `secondary` the columns; the move generator swaps them
+/// to find down moves as if they were across.
+#[derive(Clone, Debug)]
 pub struct Board {
-    pub primary: [[Letter; 15]; 15],
-    pub secondary: [[Letter; 15]; 15],
-    pub across: bool,
+    pub(crate) primary: Grid<Letter>,
+    pub(crate) secondary: Grid<Letter>,
+    pub(crate) across: bool,
+    ruleset: Arc<Ruleset>,
 }
 
 impl Board {
+    /// An empty board with the default (French) ruleset.
     pub fn new() -> Board {
+        Board::with_ruleset(Arc::new(Ruleset::default()))
+    }
+
+    /// An empty board played under `ruleset`, which also gives its size.
+    pub fn with_ruleset(ruleset: Arc<Ruleset>) -> Board {
+        let size = ruleset.board_size();
         Board {
-            primary: [[0; 15]; 15],
-            secondary: [[0; 15]; 15],
+            primary: Grid::new(size, 0),
+            secondary: Grid::new(size, 0),
             across: true,
+            ruleset,
         }
     }
 
-    pub fn play(&mut self, word: &str, row_index: usize, column_index: usize, across: bool) {
-        if across {
-            for (index, letter) in word.chars().enumerate() {
-                self.primary[row_index][column_index + index] = Letter::from_char(letter);
-                self.secondary[column_index + index][row_index] = Letter::from_char(letter);
+    /// The number of rows of the board, which is also its number of columns.
+    pub fn size(&self) -> usize {
+        self.primary.size()
+    }
+
+    /// The square the first word must cover.
+    pub fn centre(&self) -> (usize, usize) {
+        (self.size() / 2, self.size() / 2)
+    }
+
+    /// Writes a tile on a square of `primary`.
+    pub(crate) fn set(&mut self, row_index: usize, column_index: usize, letter: Letter) {
+        self.primary[(row_index, column_index)] = letter;
+        self.secondary[(column_index, row_index)] = letter;
+    }
+
+    pub fn ruleset(&self) -> &Ruleset {
+        &self.ruleset
+    }
+
+    /// The ruleset, to share it with another board.
+    pub fn shared_ruleset(&self) -> Arc<Ruleset> {
+        Arc::clone(&self.ruleset)
+    }
+
+    /// The premium of a square of `primary`, whichever way the board is
+    /// turned.
+    pub(crate) fn premium(&self, row_index: usize, column_index: usize) -> SquareType {
+        if self.across {
+            self.ruleset.premium_squares[(row_index, column_index)]
+        } else {
+            self.ruleset.premium_squares[(column_index, row_index)]
+        }
+    }
+
+    /// Writes `word` on the board starting from the given square, without
+    /// checking it against the rules. See [`Board::try_play`] for that.
+    pub fn play(
+        &mut self,
+        word: &str,
+        row_index: usize,
+        column_index: usize,
+        across: bool,
+    ) -> Result<(), Error> {
+        let letters = self.ruleset.alphabet.parse(word)?;
+        check_bounds(self.size(), row_index, column_index, across, letters.len())?;
+        for (index, letter) in letters.into_iter().enumerate() {
+            if across {
+                self.set(row_index, column_index + index, letter);
+            } else {
+                self.set(row_index + index, column_index, letter);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks `word` against the rules of the game without playing it:
+    /// it must fit on the board, agree with the tiles it crosses, a blank
+    /// only agreeing with the same blank, place at least one tile, cover the
+    /// centre on the first move or touch existing tiles afterwards, and every
+    /// word it forms must be in `lexicon`.
+    ///
+    /// The board must not be rotated.
+    pub fn validate_move(
+        &self,
+        lexicon: &Lexicon,
+        word: &str,
+        row_index: usize,
+        column_index: usize,
+        across: bool,
+    ) -> Result<Placement, Error> {
+        let placement = self.check_move(lexicon, word, row_index, column_index, across)?;
+        match placement.phonies.first() {
+            Some(phony) => Err(PlacementError::UnknownWord(phony.clone()).into()),
+            None => Ok(placement),
+        }
+    }
+
+    /// Checks `word` like [`Board::validate_move`], except that words
+    /// missing from `lexicon` are listed in [`Placement::phonies`] instead
+    /// of making it fail, as when judging a challenge.
+    pub fn check_move(
+        &self,
+        lexicon: &Lexicon,
+        word: &str,
+        row_index: usize,
+        column_index: usize,
+        across: bool,
+    ) -> Result<Placement, Error> {
+        let letters = self.ruleset.alphabet.parse(word)?;
+        if letters.contains(&BLANK) {
+            return Err(Error::InvalidCharacter('?'));
+        }
+        check_bounds(self.size(), row_index, column_index, across, letters.len())?;
+
+        let mut after = self.clone();
+        let mut new_squares = Vec::new();
+        let mut rack_tiles = Vec::new();
+        for (index, letter) in letters.iter().enumerate() {
+            let (row, column) = if across {
+                (row_index, column_index + index)
+            } else {
+                (row_index + index, column_index)
+            };
+            let existing = self.primary[(row, column)];
+            if existing == 0 {
+                after.set(row, column, *letter);
+                new_squares.push((row, column));
+                rack_tiles.push(if letter.is_blank() { BLANK } else { *letter });
+            } else if existing != *letter {
+                return Err(PlacementError::Conflict {
+                    row_index: row,
+                    column_index: column,
+                    existing: self.ruleset.alphabet.token(existing).to_string(),
+                    letter: self.ruleset.alphabet.token(*letter).to_string(),
+                }
+                .into());
+            }
+        }
+        if new_squares.is_empty() {
+            return Err(PlacementError::NoNewTile.into());
+        }
+
+        if self.is_empty() {
+            if !new_squares.contains(&self.centre()) {
+                return Err(PlacementError::MissingCentre.into());
+            }
+        } else if !new_squares
+            .iter()
+            .any(|&(row, column)| self.has_neighbour(row, column))
+        {
+            return Err(PlacementError::NotConnected.into());
+        }
+
+        // A lone tile is scored along the word it forms across the requested
+        // direction, if any.
+        let mut across = across;
+        if new_squares.len() == 1 && after.word_through(row_index, column_index, across).2.len() < 2
+        {
+            across = !across;
+        }
+        let (start_row, start_column, main_word) =
+            after.word_through(row_index, column_index, across);
+        let mut words = Vec::new();
+        if main_word.len() > 1 {
+            words.push(main_word.clone());
+        }
+        for &(row, column) in &new_squares {
+            let (_, _, cross_word) = after.word_through(row, column, !across);
+            if cross_word.len() > 1 {
+                words.push(cross_word);
             }
+        }
+        if words.is_empty() {
+            return Err(PlacementError::TooShort.into());
+        }
+        let phonies = words
+            .iter()
+            .filter(|word| !lexicon.contains_letters(word))
+            .map(|word| self.ruleset.alphabet.write(word))
+            .collect();
+
+        let mut oriented = self.clone();
+        let (row, column) = if across {
+            (start_row, start_column)
         } else {
-            for (index, letter) in word.chars().enumerate() {
-                self.primary[row_index + index][column_index] = Letter::from_char(letter);
-                self.secondary[column_index][row_index + index] = Letter::from_char(letter);
+            oriented.rotate();
+            (start_column, start_row)
+        };
+        let breakdown = score_move(
+            &oriented,
+            &main_word,
+            row,
+            column,
+            rack_tiles.len() == self.ruleset.rack_size,
+        );
+
+        Ok(Placement {
+            words: words
+                .iter()
+                .map(|word| self.ruleset.alphabet.write(word))
+                .collect(),
+            rack_tiles,
+            score: breakdown.total(),
+            breakdown,
+            phonies,
+        })
+    }
+
+    /// Validates `word` with [`Board::validate_move`] and plays it if it is legal.
+    pub fn try_play(
+        &mut self,
+        lexicon: &Lexicon,
+        word: &str,
+        row_index: usize,
+        column_index: usize,
+        across: bool,
+    ) -> Result<Placement, Error> {
+        let placement = self.validate_move(lexicon, word, row_index, column_index, across)?;
+        // Only the new tiles are written, those already on the board staying
+        // as they are.
+        let letters = self.ruleset.alphabet.parse(word)?;
+        for (index, letter) in letters.into_iter().enumerate() {
+            let (row, column) = if across {
+                (row_index, column_index + index)
+            } else {
+                (row_index + index, column_index)
+            };
+            if self.primary[(row, column)] == 0 {
+                self.set(row, column, letter);
             }
         }
+        Ok(placement)
+    }
+
+    /// Whether no tile has been played yet.
+    pub fn is_empty(&self) -> bool {
+        self.primary.cells().all(|tile| *tile == 0)
+    }
+
+    fn has_neighbour(&self, row_index: usize, column_index: usize) -> bool {
+        let last = self.size() - 1;
+        (row_index > 0 && self.primary[(row_index - 1, column_index)] != 0)
+            || (row_index < last && self.primary[(row_index + 1, column_index)] != 0)
+            || (column_index > 0 && self.primary[(row_index, column_index - 1)] != 0)
+            || (column_index < last && self.primary[(row_index, column_index + 1)] != 0)
     }
 
-    pub fn rotate(&mut self) {
-        (self.primary, self.secondary) = (self.secondary, self.primary);
+    /// The run of tiles going through a square in the given direction, with
+    /// the square it starts on.
+    fn word_through(
+        &self,
+        row_index: usize,
+        column_index: usize,
+        across: bool,
+    ) -> (usize, usize, Vec<Letter>) {
+        let line = if across {
+            self.primary.row(row_index)
+        } else {
+            self.secondary.row(column_index)
+        };
+        let index = if across { column_index } else { row_index };
+        let mut start = index;
+        while start > 0 && line[start - 1] != 0 {
+            start -= 1;
+        }
+        let mut end = index;
+        while end + 1 < line.len() && line[end + 1] != 0 {
+            end += 1;
+        }
+        let word = line[start..=end].to_vec();
+        if across {
+            (row_index, start, word)
+        } else {
+            (start, column_index, word)
+        }
+    }
+
+    /// The tile on a square, 0 when it is empty.
+    pub fn tile(&self, row_index: usize, column_index: usize) -> Letter {
+        if self.across {
+            self.primary[(row_index, column_index)]
+        } else {
+            self.secondary[(row_index, column_index)]
+        }
+    }
+
+    pub(crate) fn rotate(&mut self) {
+        std::mem::swap(&mut self.primary, &mut self.secondary);
         self.across = !self.across;
     }
 }
 
+impl Default for Board {
+    fn default() -> Self {
+        Board::new()
+    }
+}
+
+fn check_bounds(
+    size: usize,
+    row_index: usize,
+    column_index: usize,
+    across: bool,
+    length: usize,
+) -> Result<(), PlacementError> {
+    let end_index = if across { column_index } else { row_index } + length;
+    if row_index >= size || column_index >= size || end_index > size {
+        return Err(PlacementError::OutOfBounds {
+            row_index,
+            column_index,
+            across,
+            length,
+        });
+    }
+    Ok(())
+}
+
+/// Reads a board from a position file, ignoring the lines that follow the
+/// grid. See [`Position`] for the format.
+impl FromStr for Board {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Ok(text.parse::<Position>()?.board)
+    }
+}
+
 impl Display for Board {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        const TOP_BORDER: &str = "┌───┬───┬───┬───┬───┬───┬───┬───┬───┬───┬───┬───┬───┬───┬───┐";
-        const MIDDLE_BORDER: &str = "├───┼───┼───┼───┼───┼───┼───┼───┼───┼───┼───┼───┼───┼───┼───┤";
-        const BOTTOM_BORDER: &str = "└───┴───┴───┴───┴───┴───┴───┴───┴───┴───┴───┴───┴───┴───┴───┘";
-        writeln!(f, "{TOP_BORDER}")?;
-        for (row_index, row) in self.primary.iter().enumerate() {
+        let border = |left: &str, middle: &str, right: &str| {
+            format!(
+                "{left}{}───{right}",
+                format!("───{middle}").repeat(self.size() - 1)
+            )
+        };
+        writeln!(f, "{}", border("┌", "┬", "┐"))?;
+        for (row_index, row) in self.primary.rows().enumerate() {
             write!(f, "│")?;
             for (column_index, cell) in row.iter().enumerate() {
-                let prefix = match PREMIUM_SQUARES[row_index][column_index] {
-                    0 => "",
-                    1 => "\x1b[106m",
-                    2 => "\x1b[104m",
-                    3 => "\x1b[105m",
-                    4 => "\x1b[101m",
-                    _ => "",
+                let prefix = match self.premium(row_index, column_index) {
+                    SquareType::Normal => "",
+                    SquareType::DoubleLetter => "\x1b[106m",
+                    SquareType::TripleLetter => "\x1b[104m",
+                    SquareType::DoubleWord => "\x1b[105m",
+                    SquareType::TripleWord => "\x1b[101m",
+                    SquareType::QuadrupleLetter => "\x1b[44m",
+                    SquareType::QuadrupleWord => "\x1b[41m",
                 };
-                let suffix = match PREMIUM_SQUARES[row_index][column_index] {
-                    1..=4 => "\x1b[0m",
-                    _ => "",
+                let suffix = match self.premium(row_index, column_index) {
+                    SquareType::Normal => "",
+                    _ => "\x1b[0m",
                 };
-                write!(f, "{} {} {}│", prefix, cell.to_char(), suffix)?;
+                let token = self.ruleset.alphabet.token(*cell);
+                write!(f, "{prefix}{token:^3}{suffix}│")?;
             }
             writeln!(f)?;
-            if row_index < self.primary.len() - 1 {
-                writeln!(f, "{MIDDLE_BORDER}")?;
+            if row_index < self.size() - 1 {
+                writeln!(f, "{}", border("├", "┼", "┤"))?;
             } else {
-                writeln!(f, "{BOTTOM_BORDER}")?;
+                writeln!(f, "{}", border("└", "┴", "┘"))?;
             }
         }
         Ok(())
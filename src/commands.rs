@@ -0,0 +1,1198 @@
+//! The non-interactive subcommands of the command-line interface.
+
+use scrabble::endgame::Endgame;
+use scrabble::gcg::{Action, Analysis, Game, Turn};
+use scrabble::notation::{format_coordinate, MoveNotation};
+use scrabble::preendgame::Preendgame;
+use scrabble::ruleset::PRESETS;
+use scrabble::score::ScoreBreakdown;
+use scrabble::simulation::{Simulation, Strategy};
+use scrabble::tournament::{Bot, Tournament};
+use scrabble::{
+    Alphabet, Board, Error, Leaves, LegalMove, Lexicon, Move, Placement, Position, Ruleset, Solver,
+    UnseenTiles,
+};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::Arc;
+
+pub const DEFAULT_LEXICON_PATH: &str = "src/dictionaries/ods8.txt";
+
+pub const USAGE: &str = "\
+Usage:
+  scrabble [--lexicon PATH] [--rules RULES]
+      Start the interactive menu.
+  scrabble solve [--board FILE] [--rack RACK] [--top N] [--rank score|equity]
+                [--leaves FILE]
+      List the best moves for RACK (letters, ? for a blank), which defaults
+      to the rack of the position file, by score or by equity: the score
+      plus the value of the tiles kept, read from FILE or estimated.
+      Ranked by equity, exchanges and the pass are listed too, and once
+      the bag is empty the tiles kept count as minus twice their points.
+  scrabble simulate [--board FILE] [--rack RACK] [--candidates N]
+                   [--iterations N] [--plies N] [--player score|equity]
+                   [--seed N] [--threads N] [--leaves FILE]
+      Play the N best moves by equity out against random draws and rank
+      them by the average spread they lead to.
+  scrabble endgame --board FILE [--rack RACK] [--opponent RACK] [--plies N]
+      Search the best sequence of moves once the bag is empty, the
+      opponent holding the unseen tiles unless given.
+  scrabble preendgame --board FILE [--rack RACK] [--candidates N] [--plies N]
+                     [--lead N] [--threads N]
+      With 1 to a full rack of tiles left in the bag, weigh every possible
+      draw for the best moves and rank them by their chance of winning, the
+      player to move leading by N points.
+  scrabble tournament [--bots score,equity,simulation] [--games N] [--seed N]
+                     [--threads N] [--gcg DIR] [--leaves FILE]
+      Play N games between the bots, each starting in turn, and show their
+      win rates, average scores and bingos, saving each game to DIR.
+  scrabble play --board FILE --move \"8H WO(R)D\" [--save FILE]
+      Check a move against the rules and show the resulting board, saving
+      the new position to FILE if asked.
+  scrabble replay GAME.gcg [--turn N] [--top N]
+      Replay a GCG game record, comparing each move with the best one, or
+      list the moves available on turn N.
+  scrabble unseen [--board FILE] [--rack RACK]
+      Count the tiles not on the board nor on RACK, which defaults to the
+      rack of the position file: those in the bag and on the other rack.
+  scrabble check [WORD...] [--words FILE] [--board FILE --move MOVE]
+      Tell whether each word, given or listed one per line in FILE, is in
+      the lexicon, and whether each word formed by MOVE is, as when a move
+      is challenged.
+  scrabble compile WORD_LIST OUTPUT [--rules RULES]
+      Compile a word list, written in the alphabet of the rules, into a
+      file that loads faster.
+
+Options, where a subcommand takes them:
+  --lexicon PATH        The word list or compiled lexicon to use.
+  --rules RULES         french (the default), twl, collins, spanish, german
+                        or the path of a ruleset file.
+  --format text|json    How results are printed.
+
+Exit status: 0 on success, 1 when a word or move is rejected,
+2 on a usage error and 3 when a file cannot be read or written.";
+
+/// Why a command did not succeed, which decides the exit status.
+pub enum Failure {
+    /// A word is not in the lexicon or a move breaks the rules.
+    Rejected(String),
+    /// The command line is malformed.
+    Usage(String),
+    /// A file could not be read or written.
+    Error(Error),
+}
+
+impl Failure {
+    pub fn report(self) -> ExitCode {
+        match self {
+            Failure::Rejected(message) => {
+                eprintln!("{message}");
+                ExitCode::from(1)
+            }
+            Failure::Usage(message) => {
+                eprintln!("{message}\n\n{USAGE}");
+                ExitCode::from(2)
+            }
+            Failure::Error(error) => {
+                eprintln!("{error}");
+                ExitCode::from(3)
+            }
+        }
+    }
+}
+
+impl From<Error> for Failure {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::Io { .. }
+            | Error::InvalidLexicon(_)
+            | Error::InvalidWord { .. }
+            | Error::InvalidBoard { .. }
+            | Error::InvalidGame { .. }
+            | Error::InvalidRuleset { .. }
+            | Error::InvalidLeaves { .. }
+            | Error::TooManyTiles { .. }
+            | Error::WrongBagSize { .. } => Failure::Error(error),
+            Error::InvalidCharacter(_)
+            | Error::InvalidRackTile(_)
+            | Error::InvalidMove(_)
+            | Error::NotPreendgame { .. } => Failure::Usage(error.to_string()),
+            Error::IllegalPlacement(_) | Error::IllegalTurn(_) => {
+                Failure::Rejected(error.to_string())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Json,
+}
+
+/// The positional arguments and `--option value` pairs of a command.
+pub struct Arguments {
+    pub positional: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+impl Arguments {
+    /// Splits the command line, accepting only the options in `allowed`.
+    pub fn parse(arguments: &[String], allowed: &[&str]) -> Result<Arguments, Failure> {
+        let mut positional = Vec::new();
+        let mut options = HashMap::new();
+        let mut arguments = arguments.iter();
+        while let Some(argument) = arguments.next() {
+            match argument.strip_prefix("--") {
+                Some(name) => {
+                    if !allowed.contains(&name) {
+                        return Err(Failure::Usage(format!("Unknown option '{argument}'")));
+                    }
+                    let value = arguments.next().ok_or_else(|| {
+                        Failure::Usage(format!("The option '{argument}' expects a value"))
+                    })?;
+                    options.insert(name.to_string(), value.clone());
+                }
+                None => positional.push(argument.clone()),
+            }
+        }
+        Ok(Arguments {
+            positional,
+            options,
+        })
+    }
+
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    fn required(&self, name: &str) -> Result<&str, Failure> {
+        self.option(name)
+            .ok_or_else(|| Failure::Usage(format!("The option '--{name}' is required")))
+    }
+
+    fn number(&self, name: &str, default: usize) -> Result<usize, Failure> {
+        match self.option(name) {
+            None => Ok(default),
+            Some(number) => number.parse().map_err(|_| {
+                Failure::Usage(format!(
+                    "The option '--{name}' expects a number, not '{number}'"
+                ))
+            }),
+        }
+    }
+
+    /// Whether `--rank` asks for moves to be ranked by equity.
+    fn by_equity(&self) -> Result<bool, Failure> {
+        match self.option("rank") {
+            None | Some("score") => Ok(false),
+            Some("equity") => Ok(true),
+            Some(other) => Err(Failure::Usage(format!(
+                "Unknown ranking '{other}', expected 'score' or 'equity'"
+            ))),
+        }
+    }
+
+    fn format(&self) -> Result<Format, Failure> {
+        match self.option("format") {
+            None | Some("text") => Ok(Format::Text),
+            Some("json") => Ok(Format::Json),
+            Some(other) => Err(Failure::Usage(format!(
+                "Unknown format '{other}', expected 'text' or 'json'"
+            ))),
+        }
+    }
+
+    pub fn lexicon_path(&self) -> &str {
+        self.option("lexicon").unwrap_or(DEFAULT_LEXICON_PATH)
+    }
+
+    /// The lexicon of `--lexicon`, read in the alphabet of the ruleset.
+    pub fn lexicon(&self, path: &str) -> Result<Lexicon, Failure> {
+        Ok(Lexicon::open_with_alphabet(
+            path,
+            &self.ruleset()?.alphabet,
+        )?)
+    }
+
+    fn solver(&self) -> Result<Solver, Failure> {
+        let solver = Solver::new(self.lexicon(self.lexicon_path())?);
+        match self.option("leaves") {
+            None => Ok(solver),
+            Some(path) => Ok(solver.with_leaves(Leaves::load(path, &self.ruleset()?.alphabet)?)),
+        }
+    }
+
+    /// The ruleset named by `--rules`, either a preset or a file.
+    pub fn ruleset(&self) -> Result<Arc<Ruleset>, Failure> {
+        let ruleset = match self.option("rules") {
+            None => Ruleset::default(),
+            Some(name) => match Ruleset::preset(name) {
+                Some(ruleset) => ruleset,
+                None if Path::new(name).exists() => Ruleset::load(name)?,
+                None => {
+                    return Err(Failure::Usage(format!(
+                        "Unknown ruleset '{name}', expected one of {} or a ruleset file",
+                        PRESETS.join(", ")
+                    )))
+                }
+            },
+        };
+        Ok(Arc::new(ruleset))
+    }
+
+    fn position(&self) -> Result<Position, Failure> {
+        let ruleset = self.ruleset()?;
+        match self.option("board") {
+            None => Ok(Position::new(Board::with_ruleset(ruleset))),
+            Some(path) => Ok(Position::load(path, ruleset)?),
+        }
+    }
+}
+
+/// Quotes a string for JSON output.
+fn json_string(text: &str) -> String {
+    let mut quoted = String::from('"');
+    for character in text.chars() {
+        match character {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            character if (character as u32) < 0x20 => {
+                quoted.push_str(&format!("\\u{:04x}", character as u32))
+            }
+            character => quoted.push(character),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn format_move(legal_move: &LegalMove, board: &Board) -> String {
+    MoveNotation::from_move(legal_move, board).to_string()
+}
+
+fn legal_move_json(legal_move: &LegalMove, board: &Board) -> String {
+    format!(
+        "{{\"move\": {}, \"word\": {}, \"score\": {}, \"equity\": {:.1}}}",
+        json_string(&format_move(legal_move, board)),
+        json_string(&legal_move.letters),
+        legal_move.score,
+        legal_move.equity
+    )
+}
+
+/// `scrabble solve`: lists the highest-scoring moves of a position.
+pub fn solve(arguments: &[String]) -> Result<(), Failure> {
+    let arguments = Arguments::parse(
+        arguments,
+        &[
+            "board", "rack", "top", "rank", "leaves", "rules", "format", "lexicon",
+        ],
+    )?;
+    if !arguments.positional.is_empty() {
+        return Err(Failure::Usage(
+            "'solve' takes no positional argument".to_string(),
+        ));
+    }
+    let format = arguments.format()?;
+    let by_equity = arguments.by_equity()?;
+    let top = arguments.number("top", 20)?;
+    let mut position = arguments.position()?;
+    if let Some(rack) = arguments.option("rack") {
+        position.rack = Some(rack.to_string());
+    }
+    let rack = match position.rack.as_deref() {
+        Some(rack) => rack,
+        None => arguments.required("rack")?,
+    };
+    let unseen = UnseenTiles::for_position(&position)?;
+    let solver = arguments.solver()?;
+    // Exchanges and the pass score nothing and only rank by equity.
+    let mut moves = if by_equity {
+        solver.all_moves(&position.board, rack, unseen.in_bag())?
+    } else {
+        solver
+            .moves(&position.board, rack)?
+            .into_iter()
+            .map(Move::Place)
+            .collect()
+    };
+    moves.truncate(top);
+    let alphabet = &position.board.ruleset().alphabet;
+    match format {
+        Format::Text => {
+            for (index, turn) in moves.iter().enumerate() {
+                let equity = if by_equity {
+                    format!(", equity {:.1}", turn.equity())
+                } else {
+                    String::new()
+                };
+                let described = match turn {
+                    Move::Place(legal_move) => format_move(legal_move, &position.board),
+                    Move::Exchange { thrown, kept, .. } if kept.is_empty() => {
+                        format!("exchange {}", alphabet.write(thrown))
+                    }
+                    Move::Exchange { thrown, kept, .. } => format!(
+                        "exchange {}, keeping {}",
+                        alphabet.write(thrown),
+                        alphabet.write(kept)
+                    ),
+                    Move::Pass { .. } => "pass".to_string(),
+                };
+                println!(
+                    "{:>3}. {described} ({} pts{equity})",
+                    index + 1,
+                    turn.score()
+                );
+            }
+        }
+        Format::Json => {
+            let moves: Vec<String> = moves
+                .iter()
+                .map(|turn| match turn {
+                    Move::Place(legal_move) => legal_move_json(legal_move, &position.board),
+                    Move::Exchange {
+                        thrown,
+                        kept,
+                        equity,
+                    } => format!(
+                        "{{\"exchange\": {}, \"keep\": {}, \"score\": 0, \"equity\": {:.1}}}",
+                        json_string(&alphabet.write(thrown)),
+                        json_string(&alphabet.write(kept)),
+                        equity
+                    ),
+                    Move::Pass { equity } => {
+                        format!("{{\"pass\": true, \"score\": 0, \"equity\": {equity:.1}}}")
+                    }
+                })
+                .collect();
+            println!("[{}]", moves.join(", "));
+        }
+    }
+    Ok(())
+}
+
+fn placement_json(placement: &Placement, alphabet: &Alphabet) -> String {
+    let words: Vec<String> = placement
+        .words
+        .iter()
+        .map(|word| json_string(word))
+        .collect();
+    let word_scores: Vec<String> = placement
+        .breakdown
+        .words
+        .iter()
+        .map(|word| {
+            format!(
+                "{{\"word\": {}, \"word_multiplier\": {}, \"score\": {}}}",
+                json_string(&word.word),
+                word.word_multiplier,
+                word.score
+            )
+        })
+        .collect();
+    let rack_tiles = alphabet.write(&placement.rack_tiles);
+    format!(
+        "{{\"words\": [{}], \"tiles\": {}, \"score\": {}, \"breakdown\": [{}], \
+        \"bingo_bonus\": {}}}",
+        words.join(", "),
+        json_string(&rack_tiles),
+        placement.score,
+        word_scores.join(", "),
+        placement.breakdown.bingo_bonus
+    )
+}
+
+/// Writes how a score adds up, one line per word, such as
+/// `  WORD: (W 4 + O 1 + R 1×2 + D 2) ×3 = 27`.
+pub fn describe_breakdown(breakdown: &ScoreBreakdown, alphabet: &Alphabet) -> String {
+    let mut lines = Vec::new();
+    for word in &breakdown.words {
+        let squares: Vec<String> = word
+            .squares
+            .iter()
+            .map(|square| {
+                let token = alphabet.token(square.letter);
+                match square.premium.letter_multiplier() {
+                    multiplier if square.is_new && multiplier > 1 => {
+                        format!("{token} {}×{multiplier}", square.value / multiplier)
+                    }
+                    _ => format!("{token} {}", square.value),
+                }
+            })
+            .collect();
+        let sum = match word.word_multiplier {
+            1 => squares.join(" + "),
+            multiplier => format!("({}) ×{multiplier}", squares.join(" + ")),
+        };
+        lines.push(format!("  {}: {sum} = {}", word.word, word.score));
+    }
+    if breakdown.bingo_bonus > 0 {
+        lines.push(format!("  bingo: {}", breakdown.bingo_bonus));
+    }
+    lines.join("\n")
+}
+
+/// `scrabble simulate`: ranks the best moves by Monte Carlo simulation.
+pub fn simulate(arguments: &[String]) -> Result<(), Failure> {
+    let arguments = Arguments::parse(
+        arguments,
+        &[
+            "board",
+            "rack",
+            "candidates",
+            "iterations",
+            "plies",
+            "player",
+            "seed",
+            "threads",
+            "leaves",
+            "rules",
+            "format",
+            "lexicon",
+        ],
+    )?;
+    if !arguments.positional.is_empty() {
+        return Err(Failure::Usage(
+            "'simulate' takes no positional argument".to_string(),
+        ));
+    }
+    let format = arguments.format()?;
+    let defaults = Simulation::default();
+    let simulation = Simulation {
+        candidates: arguments.number("candidates", defaults.candidates)?,
+        iterations: arguments.number("iterations", defaults.iterations)?,
+        plies: arguments.number("plies", defaults.plies)?,
+        strategy: match arguments.option("player") {
+            None | Some("equity") => Strategy::Equity,
+            Some("score") => Strategy::Greedy,
+            Some(other) => {
+                return Err(Failure::Usage(format!(
+                    "Unknown player '{other}', expected 'score' or 'equity'"
+                )))
+            }
+        },
+        seed: arguments.number("seed", defaults.seed as usize)? as u64,
+        threads: arguments.number("threads", defaults.threads)?,
+    };
+    let mut position = arguments.position()?;
+    if let Some(rack) = arguments.option("rack") {
+        position.rack = Some(rack.to_string());
+    }
+    let rack = match position.rack.as_deref() {
+        Some(rack) => rack,
+        None => arguments.required("rack")?,
+    };
+    UnseenTiles::for_position(&position)?;
+    let solver = arguments.solver()?;
+    let simulated = simulation.run(&solver, &position.board, rack)?;
+    match format {
+        Format::Text => {
+            for (index, simulated) in simulated.iter().enumerate() {
+                let legal_move = &simulated.legal_move;
+                println!(
+                    "{:>3}. {} ({} pts, equity {:.1}, spread {:+.1})",
+                    index + 1,
+                    format_move(legal_move, &position.board),
+                    legal_move.score,
+                    legal_move.equity,
+                    simulated.average_spread
+                );
+            }
+        }
+        Format::Json => {
+            let moves: Vec<String> = simulated
+                .iter()
+                .map(|simulated| {
+                    format!(
+                        "{{\"move\": {}, \"word\": {}, \"score\": {}, \"equity\": {:.1}, \
+                         \"spread\": {:.2}, \"iterations\": {}}}",
+                        json_string(&format_move(&simulated.legal_move, &position.board)),
+                        json_string(&simulated.legal_move.letters),
+                        simulated.legal_move.score,
+                        simulated.legal_move.equity,
+                        simulated.average_spread,
+                        simulated.iterations
+                    )
+                })
+                .collect();
+            println!("[{}]", moves.join(", "));
+        }
+    }
+    Ok(())
+}
+
+/// Writes a turn of an endgame sequence.
+fn format_turn(turn: &Option<LegalMove>, board: &Board) -> String {
+    match turn {
+        Some(legal_move) => format!(
+            "{} ({} pts)",
+            format_move(legal_move, board),
+            legal_move.score
+        ),
+        None => "pass".to_string(),
+    }
+}
+
+/// `scrabble endgame`: searches the best sequence of moves to the end of the
+/// game.
+pub fn endgame(arguments: &[String]) -> Result<(), Failure> {
+    let arguments = Arguments::parse(
+        arguments,
+        &[
+            "board", "rack", "opponent", "plies", "rules", "format", "lexicon",
+        ],
+    )?;
+    if !arguments.positional.is_empty() {
+        return Err(Failure::Usage(
+            "'endgame' takes no positional argument".to_string(),
+        ));
+    }
+    let format = arguments.format()?;
+    let endgame = Endgame {
+        max_plies: arguments.number("plies", Endgame::default().max_plies)?,
+    };
+    let mut position = arguments.position()?;
+    if let Some(rack) = arguments.option("rack") {
+        position.rack = Some(rack.to_string());
+    }
+    let rack = match position.rack.clone() {
+        Some(rack) => rack,
+        None => arguments.required("rack")?.to_string(),
+    };
+    let unseen = UnseenTiles::for_position(&position)?;
+    let alphabet = &position.board.ruleset().alphabet;
+    let opponent_tiles = match arguments.option("opponent") {
+        Some(rack) => alphabet.parse(rack)?,
+        None if unseen.in_bag() == 0 => unseen.tiles(),
+        None => {
+            return Err(Failure::Usage(format!(
+                "The bag is not empty: {} tiles are unseen. Give the opponent's rack with \
+                 '--opponent'.",
+                unseen.total()
+            )))
+        }
+    };
+    let solver = arguments.solver()?;
+    let opponent_rack = alphabet.write(&opponent_tiles);
+    let solution = endgame.solve_for_tiles(
+        &solver,
+        &position.board,
+        &alphabet.parse(&rack)?,
+        &opponent_tiles,
+    )?;
+    // Each turn is written on the board it is played on.
+    let mut board = position.board.clone();
+    let mut turns = Vec::new();
+    for turn in &solution.principal_variation {
+        turns.push(format_turn(turn, &board));
+        if let Some(legal_move) = turn {
+            board.play(
+                &legal_move.letters,
+                legal_move.row_index,
+                legal_move.column_index,
+                legal_move.across,
+            )?;
+        }
+    }
+    match format {
+        Format::Text => {
+            println!(
+                "{rack} against {opponent_rack}: spread {:+} ({}, {} plies, {} positions)",
+                solution.spread,
+                if solution.exact { "exact" } else { "estimated" },
+                solution.plies,
+                solution.nodes
+            );
+            for (index, turn) in turns.iter().enumerate() {
+                println!("{:>3}. {turn}", index + 1);
+            }
+        }
+        Format::Json => {
+            let turns: Vec<String> = turns.iter().map(|turn| json_string(turn)).collect();
+            println!(
+                "{{\"spread\": {}, \"exact\": {}, \"plies\": {}, \"nodes\": {}, \
+                 \"sequence\": [{}]}}",
+                solution.spread,
+                solution.exact,
+                solution.plies,
+                solution.nodes,
+                turns.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// `scrabble preendgame`: ranks the best moves by enumerating the draws left.
+pub fn preendgame(arguments: &[String]) -> Result<(), Failure> {
+    let arguments = Arguments::parse(
+        arguments,
+        &[
+            "board",
+            "rack",
+            "candidates",
+            "plies",
+            "lead",
+            "threads",
+            "leaves",
+            "rules",
+            "format",
+            "lexicon",
+        ],
+    )?;
+    if !arguments.positional.is_empty() {
+        return Err(Failure::Usage(
+            "'preendgame' takes no positional argument".to_string(),
+        ));
+    }
+    let format = arguments.format()?;
+    let defaults = Preendgame::default();
+    let preendgame = Preendgame {
+        candidates: arguments.number("candidates", defaults.candidates)?,
+        endgame: Endgame {
+            max_plies: arguments.number("plies", defaults.endgame.max_plies)?,
+        },
+        threads: arguments.number("threads", defaults.threads)?,
+    };
+    let lead = match arguments.option("lead") {
+        None => 0,
+        Some(lead) => lead.parse().map_err(|_| {
+            Failure::Usage(format!(
+                "The option '--lead' expects a number, not '{lead}'"
+            ))
+        })?,
+    };
+    let mut position = arguments.position()?;
+    if let Some(rack) = arguments.option("rack") {
+        position.rack = Some(rack.to_string());
+    }
+    let rack = match position.rack.as_deref() {
+        Some(rack) => rack,
+        None => arguments.required("rack")?,
+    };
+    UnseenTiles::for_position(&position)?;
+    let solver = arguments.solver()?;
+    let analysed = preendgame.run(&solver, &position.board, rack, lead)?;
+    match format {
+        Format::Text => {
+            for (index, analysed) in analysed.iter().enumerate() {
+                let legal_move = &analysed.legal_move;
+                println!(
+                    "{:>3}. {} ({} pts, wins {:.1}%, spread {:+.1}, {} draws)",
+                    index + 1,
+                    format_move(legal_move, &position.board),
+                    legal_move.score,
+                    100.0 * analysed.win_probability,
+                    analysed.expected_spread,
+                    analysed.outcomes
+                );
+            }
+        }
+        Format::Json => {
+            let moves: Vec<String> = analysed
+                .iter()
+                .map(|analysed| {
+                    format!(
+                        "{{\"move\": {}, \"word\": {}, \"score\": {}, \"win_probability\": \
+                         {:.4}, \"spread\": {:.2}, \"outcomes\": {}}}",
+                        json_string(&format_move(&analysed.legal_move, &position.board)),
+                        json_string(&analysed.legal_move.letters),
+                        analysed.legal_move.score,
+                        analysed.win_probability,
+                        analysed.expected_spread,
+                        analysed.outcomes
+                    )
+                })
+                .collect();
+            println!("[{}]", moves.join(", "));
+        }
+    }
+    Ok(())
+}
+
+/// `scrabble play`: validates a move and shows the board once it is played.
+pub fn play(arguments: &[String]) -> Result<(), Failure> {
+    let arguments = Arguments::parse(
+        arguments,
+        &["board", "move", "save", "rules", "format", "lexicon"],
+    )?;
+    if !arguments.positional.is_empty() {
+        return Err(Failure::Usage(
+            "'play' takes no positional argument".to_string(),
+        ));
+    }
+    let format = arguments.format()?;
+    let mut position = arguments.position()?;
+    UnseenTiles::for_position(&position)?;
+    let alphabet = position.board.ruleset().alphabet.clone();
+    let notation = MoveNotation::parse(arguments.required("move")?, &alphabet)?;
+    if !notation.agrees_with(&position.board) {
+        return Err(Failure::Rejected(format!(
+            "The letters between parentheses in '{notation}' are not the tiles on the board"
+        )));
+    }
+    let solver = arguments.solver()?;
+    let placement = solver.play(
+        &mut position.board,
+        &notation.word(),
+        notation.row_index,
+        notation.column_index,
+        notation.across,
+    )?;
+    if let Some(rack) = &mut position.rack {
+        let mut tiles = alphabet.parse(rack)?;
+        for tile in &placement.rack_tiles {
+            match tiles.iter().position(|rack_tile| rack_tile == tile) {
+                Some(index) => {
+                    tiles.remove(index);
+                }
+                None => {
+                    return Err(Failure::Rejected(format!(
+                        "The rack {rack} does not hold the tile {}",
+                        alphabet.token(*tile)
+                    )))
+                }
+            }
+        }
+        *rack = alphabet.write(&tiles);
+    }
+    if let Some(path) = arguments.option("save") {
+        position.save(path)?;
+    }
+    match format {
+        Format::Text => {
+            let rack_tiles = alphabet.write(&placement.rack_tiles);
+            println!(
+                "{} scores {} points using the tiles {}",
+                placement.words.join(", "),
+                placement.score,
+                rack_tiles
+            );
+            println!("{}", describe_breakdown(&placement.breakdown, &alphabet));
+            print!("{}", position.board);
+        }
+        Format::Json => println!("{}", placement_json(&placement, &alphabet)),
+    }
+    Ok(())
+}
+
+/// `scrabble tournament`: plays bots against each other and compares them.
+pub fn tournament(arguments: &[String]) -> Result<(), Failure> {
+    let arguments = Arguments::parse(
+        arguments,
+        &[
+            "bots",
+            "games",
+            "seed",
+            "threads",
+            "gcg",
+            "candidates",
+            "iterations",
+            "plies",
+            "leaves",
+            "rules",
+            "format",
+            "lexicon",
+        ],
+    )?;
+    if !arguments.positional.is_empty() {
+        return Err(Failure::Usage(
+            "'tournament' takes no positional argument".to_string(),
+        ));
+    }
+    let format = arguments.format()?;
+    let simulation_defaults = Simulation::default();
+    // Games are played in parallel already, so each simulation runs on the
+    // thread of its game.
+    let simulation = Simulation {
+        candidates: arguments.number("candidates", simulation_defaults.candidates)?,
+        iterations: arguments.number("iterations", simulation_defaults.iterations)?,
+        plies: arguments.number("plies", simulation_defaults.plies)?,
+        threads: 1,
+        ..simulation_defaults
+    };
+    let bots = arguments
+        .option("bots")
+        .unwrap_or("score,equity")
+        .split(',')
+        .map(|name| match name.trim() {
+            "score" => Ok(Bot::Greedy),
+            "equity" => Ok(Bot::Equity),
+            "simulation" => Ok(Bot::Simulation(simulation.clone())),
+            other => Err(Failure::Usage(format!(
+                "Unknown bot '{other}', expected 'score', 'equity' or 'simulation'"
+            ))),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let defaults = Tournament::default();
+    let tournament = Tournament {
+        bots,
+        games: arguments.number("games", defaults.games)?,
+        seed: arguments.number("seed", defaults.seed as usize)? as u64,
+        threads: arguments.number("threads", defaults.threads)?,
+    };
+    let solver = arguments.solver()?;
+    let result = tournament.run(&solver, arguments.ruleset()?)?;
+    if let Some(directory) = arguments.option("gcg") {
+        let io_error = |path: &str, source| Error::Io {
+            path: path.to_string(),
+            source,
+        };
+        std::fs::create_dir_all(directory).map_err(|source| io_error(directory, source))?;
+        for (index, game) in result.games.iter().enumerate() {
+            let path = Path::new(directory).join(format!("game-{:04}.gcg", index + 1));
+            let path = path.to_string_lossy();
+            std::fs::write(path.as_ref(), &game.gcg).map_err(|source| io_error(&path, source))?;
+        }
+    }
+    let names: Vec<String> = tournament
+        .bots
+        .iter()
+        .enumerate()
+        .map(|(index, bot)| format!("{}{}", bot.name(), index + 1))
+        .collect();
+    match format {
+        Format::Text => {
+            println!("{} games", result.games.len());
+            for (name, standing) in names.iter().zip(&result.standings) {
+                println!(
+                    "{name}: wins {:.1}%, average score {:.1}, {:.2} bingos per game",
+                    100.0 * standing.win_rate(),
+                    standing.average_score(),
+                    standing.bingos_per_game()
+                );
+            }
+        }
+        Format::Json => {
+            let standings: Vec<String> = names
+                .iter()
+                .zip(&result.standings)
+                .map(|(name, standing)| {
+                    format!(
+                        "{{\"bot\": {}, \"games\": {}, \"wins\": {:.1}, \"win_rate\": {:.4}, \
+                        \"average_score\": {:.1}, \"bingos\": {}}}",
+                        json_string(name),
+                        standing.games,
+                        standing.wins,
+                        standing.win_rate(),
+                        standing.average_score(),
+                        standing.bingos
+                    )
+                })
+                .collect();
+            println!("[{}]", standings.join(", "));
+        }
+    }
+    Ok(())
+}
+
+/// `scrabble unseen`: counts the tiles the player to move has not seen.
+pub fn unseen(arguments: &[String]) -> Result<(), Failure> {
+    let arguments = Arguments::parse(arguments, &["board", "rack", "rules", "format"])?;
+    if !arguments.positional.is_empty() {
+        return Err(Failure::Usage(
+            "'unseen' takes no positional argument".to_string(),
+        ));
+    }
+    let format = arguments.format()?;
+    let mut position = arguments.position()?;
+    if let Some(rack) = arguments.option("rack") {
+        position.rack = Some(rack.to_string());
+    }
+    let unseen = UnseenTiles::for_position(&position)?;
+    let alphabet = &position.board.ruleset().alphabet;
+    match format {
+        Format::Text => {
+            println!(
+                "{} unseen tiles, {} in the bag",
+                unseen.total(),
+                unseen.in_bag()
+            );
+            for (tile, count) in unseen.counts() {
+                println!("{}: {count}", alphabet.token(tile));
+            }
+        }
+        Format::Json => {
+            let counts: Vec<String> = unseen
+                .counts()
+                .map(|(tile, count)| format!("{}: {count}", json_string(alphabet.token(tile))))
+                .collect();
+            println!(
+                "{{\"unseen\": {}, \"bag\": {}, \"tiles\": {{{}}}}}",
+                unseen.total(),
+                unseen.in_bag(),
+                counts.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// `scrabble check`: tells whether each word is in the lexicon.
+pub fn check(arguments: &[String]) -> Result<(), Failure> {
+    let arguments = Arguments::parse(
+        arguments,
+        &["words", "board", "move", "rules", "format", "lexicon"],
+    )?;
+    let mut words = arguments.positional.clone();
+    if let Some(path) = arguments.option("words") {
+        let text = std::fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        words.extend(
+            text.lines()
+                .map(str::trim)
+                .filter(|word| !word.is_empty())
+                .map(str::to_string),
+        );
+    }
+    if words.is_empty() && arguments.option("move").is_none() {
+        return Err(Failure::Usage(
+            "'check' expects at least one word, '--words FILE' or '--move MOVE'".to_string(),
+        ));
+    }
+    let format = arguments.format()?;
+    let mut results: Vec<(String, bool)> = Vec::new();
+    let (loaded, solver);
+    let lexicon = match arguments.option("move") {
+        None => {
+            loaded = arguments.lexicon(arguments.lexicon_path())?;
+            &loaded
+        }
+        Some(text) => {
+            // Every word the move forms is judged, as for a challenge.
+            let position = arguments.position()?;
+            let notation = MoveNotation::parse(text, &position.board.ruleset().alphabet)?;
+            if !notation.agrees_with(&position.board) {
+                return Err(Failure::Rejected(format!(
+                    "The letters between parentheses in '{notation}' are not the tiles on the board"
+                )));
+            }
+            solver = arguments.solver()?;
+            let placement = solver.check(
+                &position.board,
+                &notation.word(),
+                notation.row_index,
+                notation.column_index,
+                notation.across,
+            )?;
+            for word in placement.words {
+                let valid = !placement.phonies.contains(&word);
+                results.push((word, valid));
+            }
+            solver.lexicon()
+        }
+    };
+    results.extend(words.into_iter().map(|word| {
+        let valid = lexicon.contains(&word.to_uppercase());
+        (word, valid)
+    }));
+    match format {
+        Format::Text => {
+            for (word, valid) in &results {
+                println!("{word}: {}", if *valid { "valid" } else { "invalid" });
+            }
+        }
+        Format::Json => {
+            let results: Vec<String> = results
+                .iter()
+                .map(|(word, valid)| format!("{}: {valid}", json_string(word)))
+                .collect();
+            println!("{{{}}}", results.join(", "));
+        }
+    }
+    match results.iter().filter(|(_, valid)| !valid).count() {
+        0 => Ok(()),
+        1 => Err(Failure::Rejected(
+            "1 word is not in the lexicon".to_string(),
+        )),
+        count => Err(Failure::Rejected(format!(
+            "{count} words are not in the lexicon"
+        ))),
+    }
+}
+
+fn describe_turn(game: &Game, turn: &Turn) -> String {
+    let action = match &turn.action {
+        Action::Play {
+            row_index,
+            column_index,
+            across,
+            word,
+        } => format!(
+            "{} {word}",
+            format_coordinate(*row_index, *column_index, *across)
+        ),
+        Action::Pass => "pass".to_string(),
+        Action::Exchange(tiles) => format!("exchange {tiles}"),
+        Action::Withdrawn => "move withdrawn".to_string(),
+        Action::ChallengeBonus => "challenge bonus".to_string(),
+        Action::TimePenalty => "time penalty".to_string(),
+        Action::EndRack(tiles) => format!("tiles left ({tiles})"),
+    };
+    format!(
+        "{}: {}{action} {:+} ({})",
+        game.players[turn.player].nickname,
+        turn.rack
+            .as_ref()
+            .map_or(String::new(), |rack| format!("{rack} ")),
+        turn.score,
+        turn.total
+    )
+}
+
+fn analysis_json(
+    game: &Game,
+    board: &Board,
+    turn_index: usize,
+    analysis: &Analysis,
+    top: usize,
+) -> String {
+    let moves: Vec<String> = analysis
+        .moves
+        .iter()
+        .take(top)
+        .map(|legal_move| legal_move_json(legal_move, board))
+        .collect();
+    format!(
+        "{{\"turn\": {}, \"player\": {}, \"played\": {}, \"moves\": [{}]}}",
+        turn_index + 1,
+        json_string(&game.players[game.turns[turn_index].player].nickname),
+        match analysis.played {
+            Some(index) => legal_move_json(&analysis.moves[index], board),
+            None => "null".to_string(),
+        },
+        moves.join(", ")
+    )
+}
+
+/// `scrabble replay`: replays a GCG game record and compares each move with
+/// the best one, or lists the moves available on a single turn.
+pub fn replay(arguments: &[String]) -> Result<(), Failure> {
+    let arguments = Arguments::parse(arguments, &["turn", "top", "rules", "format", "lexicon"])?;
+    let [path] = arguments.positional.as_slice() else {
+        return Err(Failure::Usage(
+            "'replay' expects the path of a GCG file".to_string(),
+        ));
+    };
+    let format = arguments.format()?;
+    let top = arguments.number("top", 10)?;
+    let game = Game::load(path)?;
+    let turn = match arguments.number("turn", 0)? {
+        0 if arguments.option("turn").is_none() => None,
+        turn if (1..=game.turns.len()).contains(&turn) => Some(turn - 1),
+        turn => {
+            return Err(Failure::Usage(format!(
+                "The game has {} turns, there is no turn {turn}",
+                game.turns.len()
+            )))
+        }
+    };
+    let solver = arguments.solver()?;
+    let positions = game.replay(solver.lexicon(), arguments.ruleset()?)?;
+
+    if let Some(turn_index) = turn {
+        let turn = &game.turns[turn_index];
+        let analysis = game.analyse(&solver, &positions[turn_index], turn_index)?;
+        match format {
+            Format::Text => {
+                print!("{}", positions[turn_index].board);
+                println!("{:>3}. {}", turn_index + 1, describe_turn(&game, turn));
+                if let Some(analysis) = &analysis {
+                    for (index, legal_move) in analysis.moves.iter().enumerate().take(top) {
+                        println!(
+                            "{} {:>3}. {} ({} pts)",
+                            if analysis.played == Some(index) {
+                                '*'
+                            } else {
+                                ' '
+                            },
+                            index + 1,
+                            format_move(legal_move, &positions[turn_index].board),
+                            legal_move.score
+                        );
+                    }
+                    if analysis.played.is_none() {
+                        println!("The played move is not a legal move.");
+                    }
+                }
+            }
+            Format::Json => match &analysis {
+                Some(analysis) => println!(
+                    "{}",
+                    analysis_json(
+                        &game,
+                        &positions[turn_index].board,
+                        turn_index,
+                        analysis,
+                        top
+                    )
+                ),
+                None => println!("null"),
+            },
+        }
+        return Ok(());
+    }
+
+    let mut analyses = Vec::new();
+    for (turn_index, turn) in game.turns.iter().enumerate() {
+        let analysis = game.analyse(&solver, &positions[turn_index], turn_index)?;
+        match format {
+            Format::Text => {
+                println!("{:>3}. {}", turn_index + 1, describe_turn(&game, turn));
+                let Some(analysis) = analysis else {
+                    continue;
+                };
+                let played = match analysis.played {
+                    Some(index) => format!(
+                        "ranked {} of {} ({} pts)",
+                        index + 1,
+                        analysis.moves.len(),
+                        analysis.moves[index].score
+                    ),
+                    None => "not a legal move".to_string(),
+                };
+                match analysis.moves.first() {
+                    Some(best) => println!(
+                        "     {played}, best: {} ({} pts)",
+                        format_move(best, &positions[turn_index].board),
+                        best.score
+                    ),
+                    None => println!("     {played}, no legal move"),
+                }
+            }
+            Format::Json => {
+                if let Some(analysis) = analysis {
+                    analyses.push(analysis_json(
+                        &game,
+                        &positions[turn_index].board,
+                        turn_index,
+                        &analysis,
+                        1,
+                    ));
+                }
+            }
+        }
+    }
+    if format == Format::Json {
+        println!("[{}]", analyses.join(", "));
+    }
+    Ok(())
+}
+
+/// `scrabble compile WORD_LIST OUTPUT` turns a plain word list into a
+/// compiled lexicon that loads without rebuilding the word graphs.
+pub fn compile(arguments: &[String]) -> Result<(), Failure> {
+    let arguments = Arguments::parse(arguments, &["rules"])?;
+    let [input, output] = arguments.positional.as_slice() else {
+        return Err(Failure::Usage(
+            "'compile' expects a word list and an output path".to_string(),
+        ));
+    };
+    let lexicon = Lexicon::from_file(input, &arguments.ruleset()?.alphabet)?.with_gaddag();
+    Ok(lexicon.save(output)?)
+}
@@ -0,0 +1,301 @@
+//! The compact word graph backing the lexicon.
+
+use crate::error::Error;
+use crate::letter::{letters_in, Letter, LetterSet};
+use crate::lexicon::{NodeId, WordGraph};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+struct DawgNode {
+    is_terminal: bool,
+    letters: LetterSet,
+    first_edge: u32,
+}
+
+/// A minimized directed acyclic word graph.
+///
+/// Each node stores the set of letters labelling its outgoing edges as a
+/// bitmask, and its children sit contiguously in `edges` in ascending letter
+/// order, so following an edge is a popcount rather than a hash lookup.
+pub struct Dawg {
+    nodes: Vec<DawgNode>,
+    edges: Vec<NodeId>,
+    root: NodeId,
+}
+
+impl Dawg {
+    /// Builds the graph from sequences of letters sorted in ascending order.
+    pub fn from_sorted<'a, I>(sequences: I) -> Dawg
+    where
+        I: IntoIterator<Item = &'a [Letter]>,
+    {
+        let mut builder = DawgBuilder::new();
+        for sequence in sequences {
+            builder.insert(sequence);
+        }
+        builder.finish()
+    }
+
+    /// Returns every sequence accepted by the graph, in ascending order.
+    pub fn sequences(&self) -> Vec<Vec<Letter>> {
+        let mut sequences = Vec::new();
+        self.collect(self.root, &mut Vec::new(), &mut sequences);
+        sequences
+    }
+
+    fn collect(&self, node: NodeId, prefix: &mut Vec<Letter>, sequences: &mut Vec<Vec<Letter>>) {
+        if self.is_terminal(node) {
+            sequences.push(prefix.clone());
+        }
+        for letter in letters_in(self.edges(node)) {
+            prefix.push(letter);
+            self.collect(self.child(node, letter).unwrap(), prefix, sequences);
+            prefix.pop();
+        }
+    }
+
+    /// Counts the sequences accepted by the graph without listing them.
+    pub fn sequence_count(&self) -> usize {
+        let mut counts = vec![None; self.nodes.len()];
+        self.count_from(self.root, &mut counts)
+    }
+
+    fn count_from(&self, node: NodeId, counts: &mut [Option<usize>]) -> usize {
+        if let Some(count) = counts[node as usize] {
+            return count;
+        }
+        let mut count = self.is_terminal(node) as usize;
+        for letter in letters_in(self.edges(node)) {
+            count += self.count_from(self.child(node, letter).unwrap(), counts);
+        }
+        counts[node as usize] = Some(count);
+        count
+    }
+
+    /// Appends the graph to `bytes`: the node and edge counts, then each
+    /// node as its edge letters (a `u64`, terminal flag in the top bit) and
+    /// first edge index, then the edges, all little-endian and the others
+    /// `u32`s.
+    pub fn write_to(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&(self.nodes.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.edges.len() as u32).to_le_bytes());
+        for node in &self.nodes {
+            let letters = node.letters | (node.is_terminal as u64) << TERMINAL_BIT;
+            bytes.extend_from_slice(&letters.to_le_bytes());
+            bytes.extend_from_slice(&node.first_edge.to_le_bytes());
+        }
+        for edge in &self.edges {
+            bytes.extend_from_slice(&edge.to_le_bytes());
+        }
+    }
+
+    /// Reads a graph written by [`Dawg::write_to`] starting at `*position`,
+    /// which is advanced past it.
+    pub fn read_from(bytes: &[u8], position: &mut usize) -> Result<Dawg, Error> {
+        let node_count = read_u32(bytes, position)? as usize;
+        let edge_count = read_u32(bytes, position)? as usize;
+        if node_count == 0 {
+            return Err(Error::InvalidLexicon(
+                "The word graph has no root node.".to_string(),
+            ));
+        }
+        let mut nodes = Vec::with_capacity(node_count);
+        for _ in 0..node_count {
+            let letters = read_u64(bytes, position)?;
+            let first_edge = read_u32(bytes, position)?;
+            let node = DawgNode {
+                is_terminal: letters >> TERMINAL_BIT != 0,
+                letters: letters & !(1 << TERMINAL_BIT),
+                first_edge,
+            };
+            if node.first_edge as usize + node.letters.count_ones() as usize > edge_count {
+                return Err(Error::InvalidLexicon(
+                    "A node of the word graph points past its edges.".to_string(),
+                ));
+            }
+            nodes.push(node);
+        }
+        let mut edges = Vec::with_capacity(edge_count);
+        for _ in 0..edge_count {
+            let edge = read_u32(bytes, position)?;
+            if edge as usize >= node_count {
+                return Err(Error::InvalidLexicon(
+                    "An edge of the word graph points past its nodes.".to_string(),
+                ));
+            }
+            edges.push(edge);
+        }
+        Ok(Dawg {
+            nodes,
+            edges,
+            root: 0,
+        })
+    }
+}
+
+/// Bit of a serialized node's letter set holding its terminal flag.
+const TERMINAL_BIT: u32 = 63;
+
+pub(crate) fn read_u32(bytes: &[u8], position: &mut usize) -> Result<u32, Error> {
+    let chunk = bytes
+        .get(*position..*position + 4)
+        .ok_or_else(|| Error::InvalidLexicon("The file is truncated.".to_string()))?;
+    *position += 4;
+    Ok(u32::from_le_bytes(chunk.try_into().unwrap()))
+}
+
+fn read_u64(bytes: &[u8], position: &mut usize) -> Result<u64, Error> {
+    let low = read_u32(bytes, position)? as u64;
+    let high = read_u32(bytes, position)? as u64;
+    Ok(high << 32 | low)
+}
+
+impl WordGraph for Dawg {
+    fn root(&self) -> NodeId {
+        self.root
+    }
+
+    fn is_terminal(&self, node: NodeId) -> bool {
+        self.nodes[node as usize].is_terminal
+    }
+
+    fn edges(&self, node: NodeId) -> LetterSet {
+        self.nodes[node as usize].letters
+    }
+
+    fn child(&self, node: NodeId, letter: Letter) -> Option<NodeId> {
+        let node = &self.nodes[node as usize];
+        let bit: LetterSet = 1 << letter;
+        if node.letters & bit == 0 {
+            return None;
+        }
+        let offset = (node.letters & (bit - 1)).count_ones();
+        Some(self.edges[(node.first_edge + offset) as usize])
+    }
+}
+
+#[derive(Default)]
+struct BuildNode {
+    is_terminal: bool,
+    edges: Vec<(Letter, usize)>,
+}
+
+/// Incremental construction of a minimal graph from sorted input, following
+/// Daciuk et al.: once a branch can no longer be extended, its nodes are
+/// merged with equivalent ones already registered.
+pub(crate) struct DawgBuilder {
+    nodes: Vec<BuildNode>,
+    free: Vec<usize>,
+    register: HashMap<(bool, Vec<(Letter, usize)>), usize>,
+    unchecked: Vec<(usize, Letter, usize)>,
+    previous: Vec<Letter>,
+}
+
+impl DawgBuilder {
+    pub fn new() -> DawgBuilder {
+        DawgBuilder {
+            nodes: vec![BuildNode::default()],
+            free: Vec::new(),
+            register: HashMap::new(),
+            unchecked: Vec::new(),
+            previous: Vec::new(),
+        }
+    }
+
+    fn new_node(&mut self) -> usize {
+        match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.nodes.push(BuildNode::default());
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    /// Adds a sequence, which must not sort before the previous one.
+    pub fn insert(&mut self, sequence: &[Letter]) {
+        if sequence <= self.previous.as_slice() && !self.previous.is_empty() {
+            // Duplicates are ignored; anything else out of order is a caller bug.
+            debug_assert!(sequence == self.previous.as_slice(), "input must be sorted");
+            return;
+        }
+        let common_prefix = sequence
+            .iter()
+            .zip(&self.previous)
+            .take_while(|(a, b)| a == b)
+            .count();
+        self.minimize(common_prefix);
+        let mut node = match self.unchecked.last() {
+            Some(&(_, _, child)) => child,
+            None => 0,
+        };
+        for &letter in &sequence[common_prefix..] {
+            let child = self.new_node();
+            self.nodes[node].edges.push((letter, child));
+            self.unchecked.push((node, letter, child));
+            node = child;
+        }
+        self.nodes[node].is_terminal = true;
+        self.previous = sequence.to_vec();
+    }
+
+    fn minimize(&mut self, down_to: usize) {
+        while self.unchecked.len() > down_to {
+            let (parent, _, child) = self.unchecked.pop().unwrap();
+            let node = &self.nodes[child];
+            let key = (node.is_terminal, node.edges.clone());
+            match self.register.get(&key) {
+                Some(&existing) => {
+                    self.nodes[parent].edges.last_mut().unwrap().1 = existing;
+                    self.nodes[child] = BuildNode::default();
+                    self.free.push(child);
+                }
+                None => {
+                    self.register.insert(key, child);
+                }
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> Dawg {
+        self.minimize(0);
+        self.register.clear();
+
+        // Lay the reachable nodes out breadth-first, the root first.
+        let mut ids = HashMap::new();
+        let mut order = vec![0];
+        ids.insert(0, 0);
+        let mut index = 0;
+        while index < order.len() {
+            for &(_, child) in &self.nodes[order[index]].edges {
+                if let Entry::Vacant(entry) = ids.entry(child) {
+                    entry.insert(order.len() as NodeId);
+                    order.push(child);
+                }
+            }
+            index += 1;
+        }
+
+        let mut nodes = Vec::with_capacity(order.len());
+        let mut edges = Vec::new();
+        for &old in &order {
+            let node = &self.nodes[old];
+            let mut letters = 0;
+            let first_edge = edges.len() as u32;
+            for &(letter, child) in &node.edges {
+                letters |= 1 << letter;
+                edges.push(ids[&child]);
+            }
+            nodes.push(DawgNode {
+                is_terminal: node.is_terminal,
+                letters,
+                first_edge,
+            });
+        }
+        Dawg {
+            nodes,
+            edges,
+            root: 0,
+        }
+    }
+}
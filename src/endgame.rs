@@ -0,0 +1,367 @@
+//! Endgame search: once the bag is empty both racks are known, and the best
+//! move is found by looking ahead through every reply rather than by score.
+//!
+//! The search is a negamax with alpha-beta pruning over the moves of both
+//! players, passes included, deepened one ply at a time so that each pass
+//! orders the moves of the next with the best moves it found. Positions
+//! reached by different orders of moves are searched once thanks to a
+//! transposition table. The game ends when a player goes out, scoring twice
+//! the value of the other rack, or after two passes in a row, each player
+//! then losing the value of their own rack.
+
+use crate::bag::UnseenTiles;
+use crate::board::Board;
+use crate::error::Error;
+use crate::legal_moves::{add_letter_to_rack, calculate_legal_moves, LegalMove};
+use crate::letter::{Blank, Letter, BLANK};
+use crate::solver::Solver;
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// The settings of an endgame search.
+#[derive(Clone, Debug)]
+pub struct Endgame {
+    /// The deepest search tried, in moves and passes of either player.
+    pub max_plies: usize,
+}
+
+impl Default for Endgame {
+    fn default() -> Self {
+        Endgame { max_plies: 6 }
+    }
+}
+
+/// The outcome of an endgame search.
+#[derive(Clone, Debug)]
+pub struct EndgameSolution {
+    /// How many points the player to move gains on the opponent by the end
+    /// of the game, or by the end of the search if it is not `exact`.
+    pub spread: i32,
+    /// The best sequence of turns for both players, starting with the
+    /// player to move, `None` standing for a pass.
+    pub principal_variation: Vec<Option<LegalMove>>,
+    /// Whether the search reached the end of the game on every line, in
+    /// which case `spread` is the exact outcome of best play.
+    pub exact: bool,
+    /// The depth of the last completed search.
+    pub plies: usize,
+    /// The number of positions searched.
+    pub nodes: usize,
+}
+
+impl Endgame {
+    /// Finds the best sequence of moves when the player to move holds
+    /// `rack` and the opponent `opponent_rack`, both written as letters with
+    /// `?` for a blank.
+    pub fn solve(
+        &self,
+        solver: &Solver,
+        board: &Board,
+        rack: &str,
+        opponent_rack: &str,
+    ) -> Result<EndgameSolution, Error> {
+        let alphabet = &board.ruleset().alphabet;
+        self.solve_for_tiles(
+            solver,
+            board,
+            &alphabet.parse(rack)?,
+            &alphabet.parse(opponent_rack)?,
+        )
+    }
+
+    /// Finds the best sequence of moves like [`Endgame::solve`] for racks
+    /// given as tiles, [`BLANK`] standing for a blank.
+    pub fn solve_for_tiles(
+        &self,
+        solver: &Solver,
+        board: &Board,
+        rack: &[Letter],
+        opponent_rack: &[Letter],
+    ) -> Result<EndgameSolution, Error> {
+        let alphabet = &board.ruleset().alphabet;
+        let mut racks = [rack.to_vec(), opponent_rack.to_vec()];
+        for rack in &mut racks {
+            if let Some(tile) = rack.iter().find(|tile| tile.is_blank() && **tile != BLANK) {
+                return Err(Error::InvalidRackTile(alphabet.token(*tile).to_string()));
+            }
+            rack.sort_unstable();
+        }
+        // Fails if the board and both racks hold more tiles than exist.
+        UnseenTiles::new(board, &[racks[0].as_slice(), &racks[1]].concat())?;
+
+        let mut search = Search {
+            solver,
+            table: HashMap::new(),
+            nodes: 0,
+            horizon_reached: false,
+        };
+        let mut state = State {
+            board: board.clone(),
+            racks,
+            player: 0,
+            passes: 0,
+        };
+        let mut solution = EndgameSolution {
+            spread: 0,
+            principal_variation: Vec::new(),
+            exact: false,
+            plies: 0,
+            nodes: 0,
+        };
+        for plies in 1..=self.max_plies.max(1) {
+            search.horizon_reached = false;
+            solution.spread = search.negamax(&mut state, plies, -i32::MAX, i32::MAX);
+            solution.plies = plies;
+            solution.exact = !search.horizon_reached;
+            if solution.exact {
+                break;
+            }
+        }
+        solution.principal_variation = search.principal_variation(&mut state, solution.plies);
+        solution.nodes = search.nodes;
+        Ok(solution)
+    }
+}
+
+/// A position of the search: the board, both racks and who is to move.
+struct State {
+    board: Board,
+    /// The racks, sorted, of the player to move at the root and of the
+    /// opponent.
+    racks: [Vec<Letter>; 2],
+    player: usize,
+    /// Whether the previous turn was a pass.
+    passes: u8,
+}
+
+impl State {
+    fn key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.board.primary.hash(&mut hasher);
+        for rack in &self.racks {
+            hasher.write(rack);
+            hasher.write_u8(0);
+        }
+        hasher.write_usize(self.player);
+        hasher.write_u8(self.passes);
+        hasher.finish()
+    }
+
+    fn rack_value(&self, player: usize) -> i32 {
+        self.racks[player]
+            .iter()
+            .map(|tile| self.board.ruleset().letter_value(*tile) as i32)
+            .sum()
+    }
+
+    /// Places the tiles of `legal_move` and takes them off the rack of the
+    /// player to move.
+    fn play(&mut self, legal_move: &LegalMove) {
+        let rack = &mut self.racks[self.player];
+        for &(row_index, column_index, tile) in &legal_move.placed_tiles {
+            self.board.set(row_index, column_index, tile);
+            let rack_tile = if tile.is_blank() { BLANK } else { tile };
+            if let Some(position) = rack.iter().position(|other| *other == rack_tile) {
+                rack.remove(position);
+            }
+        }
+    }
+
+    fn undo(&mut self, legal_move: &LegalMove) {
+        let rack = &mut self.racks[self.player];
+        for &(row_index, column_index, tile) in &legal_move.placed_tiles {
+            self.board.set(row_index, column_index, 0);
+            rack.push(if tile.is_blank() { BLANK } else { tile });
+        }
+        rack.sort_unstable();
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+/// What the transposition table remembers of a position.
+struct Entry {
+    depth: usize,
+    value: i32,
+    bound: Bound,
+    /// The index of the best move among the moves of the position, one past
+    /// the last for a pass.
+    best: usize,
+    /// Whether every line below reached the end of the game, making the
+    /// value good at any depth.
+    complete: bool,
+}
+
+struct Search<'a> {
+    solver: &'a Solver,
+    table: HashMap<u64, Entry>,
+    nodes: usize,
+    /// Whether a line was cut short by the depth of the search.
+    horizon_reached: bool,
+}
+
+impl Search<'_> {
+    fn moves(&self, state: &State) -> Vec<LegalMove> {
+        let rack = &state.racks[state.player];
+        if rack.is_empty() {
+            return Vec::new();
+        }
+        let mut rack_tiles = HashMap::new();
+        for tile in rack {
+            add_letter_to_rack(&mut rack_tiles, *tile);
+        }
+        let mut moves = calculate_legal_moves(self.solver.lexicon(), &state.board, &mut rack_tiles)
+            .unwrap_or_default();
+        moves.sort_by_key(|legal_move| Reverse(legal_move.score));
+        moves
+    }
+
+    /// The value of `state` for the player to move, searched `depth` plies
+    /// ahead within the window from `alpha` to `beta`.
+    fn negamax(&mut self, state: &mut State, depth: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.nodes += 1;
+        let key = state.key();
+        let mut best_first = None;
+        if let Some(entry) = self.table.get(&key) {
+            if entry.complete || entry.depth >= depth {
+                let usable = match entry.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => entry.value >= beta,
+                    Bound::Upper => entry.value <= alpha,
+                };
+                if usable {
+                    self.horizon_reached |= !entry.complete;
+                    return entry.value;
+                }
+            }
+            best_first = Some(entry.best);
+        }
+        let player = state.player;
+        if depth == 0 {
+            // Left as they are, both racks would count against their owners.
+            self.horizon_reached = true;
+            return state.rack_value(1 - player) - state.rack_value(player);
+        }
+
+        let outer_horizon = std::mem::replace(&mut self.horizon_reached, false);
+        let original_alpha = alpha;
+        let moves = self.moves(state);
+        // The pass comes last, after the moves, unless it was best before.
+        let mut order: Vec<usize> = (0..=moves.len()).collect();
+        if let Some(best) = best_first.filter(|best| *best <= moves.len()) {
+            order.retain(|index| *index != best);
+            order.insert(0, best);
+        }
+        let mut best_value = -i32::MAX;
+        let mut best_index = moves.len();
+        for index in order {
+            let value = match moves.get(index) {
+                Some(legal_move) => {
+                    state.play(legal_move);
+                    let value = if state.racks[player].is_empty() {
+                        legal_move.score as i32 + 2 * state.rack_value(1 - player)
+                    } else {
+                        state.player = 1 - player;
+                        let passes = std::mem::replace(&mut state.passes, 0);
+                        let value =
+                            legal_move.score as i32 - self.negamax(state, depth - 1, -beta, -alpha);
+                        state.passes = passes;
+                        state.player = player;
+                        value
+                    };
+                    state.undo(legal_move);
+                    value
+                }
+                // A second pass in a row ends the game.
+                None if state.passes > 0 => state.rack_value(1 - player) - state.rack_value(player),
+                None => {
+                    state.player = 1 - player;
+                    state.passes = 1;
+                    let value = -self.negamax(state, depth - 1, -beta, -alpha);
+                    state.passes = 0;
+                    state.player = player;
+                    value
+                }
+            };
+            if value > best_value {
+                best_value = value;
+                best_index = index;
+            }
+            alpha = alpha.max(value);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let complete = !self.horizon_reached;
+        self.horizon_reached |= outer_horizon;
+        let bound = if best_value <= original_alpha {
+            Bound::Upper
+        } else if best_value >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.table.insert(
+            key,
+            Entry {
+                depth,
+                value: best_value,
+                bound,
+                best: best_index,
+                complete,
+            },
+        );
+        best_value
+    }
+
+    /// Follows the best moves remembered by the table from `state`.
+    fn principal_variation(&self, state: &mut State, plies: usize) -> Vec<Option<LegalMove>> {
+        let mut variation = Vec::new();
+        let mut played = Vec::new();
+        while variation.len() < plies {
+            let Some(entry) = self.table.get(&state.key()) else {
+                break;
+            };
+            let moves = self.moves(state);
+            let player = state.player;
+            match moves.into_iter().nth(entry.best) {
+                Some(legal_move) => {
+                    state.play(&legal_move);
+                    let out = state.racks[player].is_empty();
+                    played.push((player, state.passes, Some(legal_move.clone())));
+                    variation.push(Some(legal_move));
+                    state.passes = 0;
+                    if out {
+                        break;
+                    }
+                }
+                None => {
+                    played.push((player, state.passes, None));
+                    variation.push(None);
+                    if state.passes > 0 {
+                        break;
+                    }
+                    state.passes = 1;
+                }
+            }
+            state.player = 1 - player;
+        }
+        // Puts the state back as it was.
+        for (player, passes, legal_move) in played.into_iter().rev() {
+            state.player = player;
+            state.passes = passes;
+            if let Some(legal_move) = legal_move {
+                state.undo(&legal_move);
+            }
+        }
+        variation
+    }
+}
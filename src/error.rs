@@ -0,0 +1,239 @@
+//! The errors reported by the crate.
+
+use std::fmt::Display;
+
+/// Everything that can go wrong while reading user input, lexicon files or
+/// placing words on the board.
+#[derive(Debug)]
+pub enum Error {
+    /// A character that does not start a letter of the alphabet or a blank
+    /// (`?`).
+    InvalidCharacter(char),
+    /// A rack may only hold uppercase letters and blanks.
+    InvalidRackTile(String),
+    /// Reading or writing a file failed.
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    /// A line of a plain word list is not a valid word.
+    InvalidWord { line: usize, word: String },
+    /// A compiled lexicon is corrupted, truncated or of another version.
+    InvalidLexicon(String),
+    /// A board description could not be read.
+    InvalidBoard { line: usize, message: String },
+    /// A GCG game record could not be read or replayed.
+    InvalidGame { line: usize, message: String },
+    /// A ruleset file could not be read.
+    InvalidRuleset { line: usize, message: String },
+    /// A board, rack or bag holds more tiles of a letter than the ruleset
+    /// has, `count` being how many were found.
+    TooManyTiles {
+        tile: String,
+        count: usize,
+        available: u8,
+    },
+    /// The bag recorded with a position does not hold the number of tiles
+    /// left once the opponent's rack is drawn.
+    WrongBagSize { recorded: usize, expected: usize },
+    /// A pre-endgame needs from one tile to a full rack left in the bag.
+    NotPreendgame { in_bag: usize, rack_size: usize },
+    /// A table of leave values could not be read.
+    InvalidLeaves { line: usize, message: String },
+    /// A move is not written in the standard notation, such as `8H WO(R)D`.
+    InvalidMove(String),
+    /// A word cannot be placed where it was asked to go.
+    IllegalPlacement(PlacementError),
+    /// A move cannot be made by the player to move of a game.
+    IllegalTurn(TurnError),
+}
+
+/// The rule of the game broken by a placement.
+#[derive(Debug)]
+pub enum PlacementError {
+    /// The word would not fit on the board from the given square.
+    OutOfBounds {
+        row_index: usize,
+        column_index: usize,
+        across: bool,
+        length: usize,
+    },
+    /// A square of the word already holds a different tile.
+    Conflict {
+        row_index: usize,
+        column_index: usize,
+        existing: String,
+        letter: String,
+    },
+    /// Every square of the word is already occupied.
+    NoNewTile,
+    /// The first word of the game does not cover the centre square.
+    MissingCentre,
+    /// None of the new tiles touches a tile already on the board.
+    NotConnected,
+    /// The new tiles do not form any word of at least two letters.
+    TooShort,
+    /// A word formed by the placement is not in the lexicon.
+    UnknownWord(String),
+}
+
+/// The rule of the game broken by a turn, whatever the tiles placed.
+#[derive(Debug)]
+pub enum TurnError {
+    /// The game is over, so no one can move.
+    GameOver,
+    /// The rack of the player to move lacks tiles the move needs.
+    NotOnRack(String),
+    /// Too few tiles are left in the bag to exchange.
+    ExchangeNotAllowed { in_bag: usize, needed: usize },
+    /// An exchange must throw at least one tile, passing being the way to
+    /// keep them all.
+    NothingToExchange,
+    /// The last turn is not a placement that can be challenged.
+    NothingToChallenge,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidCharacter(character) => write!(
+                f,
+                "Invalid character: '{character}'. Expected a letter of the alphabet, in lowercase \
+                for a blank, or a joker ('?')."
+            ),
+            Error::InvalidRackTile(tile) => write!(
+                f,
+                "Invalid rack tile: '{tile}'. A rack holds uppercase letters and jokers ('?')."
+            ),
+            Error::Io { path, source } => write!(f, "Failed to access '{path}': {source}"),
+            Error::InvalidWord { line, word } => {
+                write!(f, "Invalid word '{word}' on line {line} of the word list.")
+            }
+            Error::InvalidLexicon(message) => write!(f, "Invalid compiled lexicon: {message}"),
+            Error::InvalidBoard { line, message } => {
+                write!(f, "Invalid board on line {line}: {message}")
+            }
+            Error::InvalidGame { line, message } => {
+                write!(f, "Invalid game record on line {line}: {message}")
+            }
+            Error::InvalidRuleset { line, message } => {
+                write!(f, "Invalid ruleset on line {line}: {message}")
+            }
+            Error::TooManyTiles {
+                tile,
+                count,
+                available,
+            } => write!(
+                f,
+                "Impossible position: {count} '{tile}' tiles are accounted for, but the ruleset \
+                only has {available}."
+            ),
+            Error::WrongBagSize { recorded, expected } => write!(
+                f,
+                "Impossible position: the bag holds {recorded} tiles, but {expected} are left \
+                once the opponent's rack is drawn."
+            ),
+            Error::NotPreendgame { in_bag: 0, .. } => {
+                write!(f, "The bag is empty: analyse the position with 'endgame'.")
+            }
+            Error::NotPreendgame { in_bag, rack_size } => write!(
+                f,
+                "{in_bag} tiles are left in the bag, where a pre-endgame has 1 to {rack_size}."
+            ),
+            Error::InvalidLeaves { line, message } => {
+                write!(f, "Invalid leave values on line {line}: {message}")
+            }
+            Error::InvalidMove(text) => write!(
+                f,
+                "Invalid move '{text}'. Expected a coordinate, such as 8H for a word across \
+                or H8 for a word down, followed by the word, with the tiles already on the \
+                board between parentheses."
+            ),
+            Error::IllegalPlacement(error) => write!(f, "{error}"),
+            Error::IllegalTurn(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl Display for PlacementError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlacementError::OutOfBounds {
+                row_index,
+                column_index,
+                across,
+                length,
+            } => write!(
+                f,
+                "A {length}-letter word played {} from row {} column {} does not fit on the board.",
+                if *across { "across" } else { "down" },
+                row_index + 1,
+                column_index + 1
+            ),
+            PlacementError::Conflict {
+                row_index,
+                column_index,
+                existing,
+                letter,
+            } => write!(
+                f,
+                "Cannot place '{letter}' on row {} column {}, which already holds '{existing}'.",
+                row_index + 1,
+                column_index + 1
+            ),
+            PlacementError::NoNewTile => {
+                write!(f, "The word only covers tiles already on the board.")
+            }
+            PlacementError::MissingCentre => {
+                write!(f, "The first word must cover the centre square.")
+            }
+            PlacementError::NotConnected => {
+                write!(f, "The word must touch a tile already on the board.")
+            }
+            PlacementError::TooShort => write!(f, "A word must be at least two letters long."),
+            PlacementError::UnknownWord(word) => {
+                write!(f, "'{word}' is not in the lexicon.")
+            }
+        }
+    }
+}
+
+impl Display for TurnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TurnError::GameOver => write!(f, "The game is over."),
+            TurnError::NotOnRack(tiles) => {
+                write!(f, "The rack does not hold the tiles {tiles}.")
+            }
+            TurnError::ExchangeNotAllowed { in_bag, needed } => write!(
+                f,
+                "Cannot exchange with {in_bag} tiles left in the bag, at least {needed} are needed."
+            ),
+            TurnError::NothingToExchange => write!(f, "An exchange needs at least one tile."),
+            TurnError::NothingToChallenge => {
+                write!(f, "The last turn is not a move that can be challenged.")
+            }
+        }
+    }
+}
+
+impl From<TurnError> for Error {
+    fn from(error: TurnError) -> Self {
+        Error::IllegalTurn(error)
+    }
+}
+
+impl From<PlacementError> for Error {
+    fn from(error: PlacementError) -> Self {
+        Error::IllegalPlacement(error)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,596 @@
+//! A whole game: the board, the bag, the racks and the scores of every
+//! player, moves being checked against the rules and applied in turn.
+//!
+//! The game ends when a player plays their last tile with the bag empty,
+//! gaining the value of every other rack while the others lose the value of
+//! their own, or after six scoreless turns in a row, every player then
+//! losing the value of their rack. Every turn is recorded with the state of
+//! the game before it, so that turns can be undone and redone.
+//!
+//! Under the void rule, words missing from the lexicon are refused. Under
+//! the single and double rules they can be played, and stay on the board
+//! unless the next player challenges them off.
+
+use crate::bag::UnseenTiles;
+use crate::board::Board;
+use crate::error::{Error, TurnError};
+use crate::leave::leave_after;
+use crate::legal_moves::{LegalMove, Move};
+use crate::letter::{Blank, Letter, BLANK};
+use crate::notation::format_coordinate;
+use crate::random::Random;
+use crate::ruleset::Ruleset;
+use crate::solver::Solver;
+use std::sync::Arc;
+
+/// The number of scoreless turns in a row that ends the game: passes,
+/// exchanges and moves scoring nothing alike.
+pub const SCORELESS_TURNS_LIMIT: usize = 6;
+
+/// How words missing from the lexicon, or phonies, are dealt with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChallengeRule {
+    /// A move forming a phony is refused, as if checked by a referee.
+    #[default]
+    Void,
+    /// A move forming a phony can be challenged off the board, and a
+    /// challenge of a valid move costs nothing.
+    Single,
+    /// Like the single rule, but a challenge of a valid move costs the
+    /// challenger their turn.
+    Double,
+}
+
+/// The outcome of a challenge recorded as a turn.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Challenge {
+    /// The move was taken back for forming these phonies.
+    Withdrawn(Vec<String>),
+    /// The move challenged was valid, and the challenger lost their turn.
+    Failed,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Player {
+    /// The tiles on the rack, sorted, [`BLANK`] for a blank.
+    pub rack: Vec<Letter>,
+    pub score: i32,
+}
+
+/// What happened during a turn.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TurnRecord {
+    pub player: usize,
+    /// The move made, its equity being its score as no leave is valued.
+    pub turn: Move,
+    /// The tiles drawn after the move.
+    pub drawn: Vec<Letter>,
+    /// The points each player gained or lost for the racks left when the
+    /// turn ended the game, empty otherwise.
+    pub end_adjustments: Vec<i32>,
+    /// For a turn settling a challenge, its outcome. A move withdrawn is
+    /// recorded again for its player, with no points and no tiles drawn.
+    pub challenge: Option<Challenge>,
+}
+
+/// Everything a turn changes.
+#[derive(Clone, Debug)]
+struct State {
+    board: Board,
+    /// The tiles left, drawn from the end.
+    bag: Vec<Letter>,
+    players: Vec<Player>,
+    to_move: usize,
+    scoreless_turns: usize,
+    over: bool,
+    random: Random,
+}
+
+/// A game between any number of players, starting with the first.
+#[derive(Clone, Debug)]
+pub struct Game {
+    state: State,
+    /// Each turn played with the state before it.
+    history: Vec<(State, TurnRecord)>,
+    /// Each turn undone with the state after it, the last undone last.
+    undone: Vec<(State, TurnRecord)>,
+    /// The name of each player in GCG records, in turn order.
+    nicknames: Vec<String>,
+    challenge_rule: ChallengeRule,
+}
+
+impl Game {
+    /// Starts a game of `players` players, at least one, on an empty board,
+    /// the bag being shuffled from `seed` and a rack dealt to each player.
+    pub fn new(ruleset: Arc<Ruleset>, players: usize, seed: u64) -> Game {
+        let mut random = Random::new(seed);
+        let mut bag = UnseenTiles::full(&ruleset).tiles();
+        random.shuffle(&mut bag);
+        let mut state = State {
+            board: Board::with_ruleset(ruleset),
+            bag,
+            players: vec![
+                Player {
+                    rack: Vec::new(),
+                    score: 0,
+                };
+                players.max(1)
+            ],
+            to_move: 0,
+            scoreless_turns: 0,
+            over: false,
+            random,
+        };
+        for player in 0..state.players.len() {
+            state.draw(player);
+        }
+        let nicknames = (1..=state.players.len())
+            .map(|number| format!("player{number}"))
+            .collect();
+        Game {
+            state,
+            history: Vec::new(),
+            undone: Vec::new(),
+            nicknames,
+            challenge_rule: ChallengeRule::default(),
+        }
+    }
+
+    /// Plays the game under `challenge_rule` rather than the void rule.
+    pub fn with_challenge_rule(mut self, challenge_rule: ChallengeRule) -> Game {
+        self.challenge_rule = challenge_rule;
+        self
+    }
+
+    pub fn challenge_rule(&self) -> ChallengeRule {
+        self.challenge_rule
+    }
+
+    /// Names the players in GCG records, in turn order. Players left
+    /// without a name keep theirs, `player1`, `player2` and so on, and
+    /// extra names are ignored.
+    pub fn with_nicknames(mut self, nicknames: &[String]) -> Game {
+        for (nickname, given) in self.nicknames.iter_mut().zip(nicknames) {
+            nickname.clone_from(given);
+        }
+        self
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.state.board
+    }
+
+    pub fn players(&self) -> &[Player] {
+        &self.state.players
+    }
+
+    /// The index of the player whose turn it is.
+    pub fn to_move(&self) -> usize {
+        self.state.to_move
+    }
+
+    /// The number of tiles left in the bag.
+    pub fn in_bag(&self) -> usize {
+        self.state.bag.len()
+    }
+
+    /// The number of scoreless turns played in a row.
+    pub fn scoreless_turns(&self) -> usize {
+        self.state.scoreless_turns
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.state.over
+    }
+
+    /// The turns played so far, first to last.
+    pub fn history(&self) -> impl Iterator<Item = &TurnRecord> {
+        self.history.iter().map(|(_, record)| record)
+    }
+
+    /// Places `word` for the player to move, see [`Board::validate_move`],
+    /// and refills their rack. Words missing from the lexicon are only
+    /// refused under the void rule.
+    pub fn play(
+        &mut self,
+        solver: &Solver,
+        word: &str,
+        row_index: usize,
+        column_index: usize,
+        across: bool,
+    ) -> Result<&TurnRecord, Error> {
+        self.check_not_over()?;
+        let board = &self.state.board;
+        let placement = match self.challenge_rule {
+            ChallengeRule::Void => solver.validate(board, word, row_index, column_index, across)?,
+            ChallengeRule::Single | ChallengeRule::Double => {
+                solver.check(board, word, row_index, column_index, across)?
+            }
+        };
+        let rack = &self.state.players[self.state.to_move].rack;
+        let leave = take_from_rack(board.ruleset(), rack, &placement.rack_tiles)?;
+        let letters = board.ruleset().alphabet.parse(word)?;
+        let placed_tiles = letters
+            .iter()
+            .enumerate()
+            .map(|(index, tile)| match across {
+                true => (row_index, column_index + index, *tile),
+                false => (row_index + index, column_index, *tile),
+            })
+            .filter(|(row_index, column_index, _)| board.tile(*row_index, *column_index) == 0)
+            .collect();
+        let legal_move = LegalMove {
+            row_index,
+            column_index,
+            across,
+            letters: board.ruleset().alphabet.write(&letters),
+            score: placement.score,
+            placed_tiles,
+            equity: placement.score as f64,
+        };
+
+        let before = self.state.clone();
+        let state = &mut self.state;
+        for &(row_index, column_index, tile) in &legal_move.placed_tiles {
+            state.board.set(row_index, column_index, tile);
+        }
+        let player = state.to_move;
+        state.players[player].rack = leave;
+        state.players[player].score += placement.score as i32;
+        let drawn = state.draw(player);
+        let end_adjustments = if state.players[player].rack.is_empty() {
+            state.go_out(player)
+        } else {
+            state.end_turn(placement.score)
+        };
+        let record = TurnRecord {
+            player,
+            turn: Move::Place(legal_move),
+            drawn,
+            end_adjustments,
+            challenge: None,
+        };
+        Ok(self.record(before, record))
+    }
+
+    /// Throws `tiles` of the player to move back in the bag for as many new
+    /// ones, which needs at least one tile to throw and a full rack left in
+    /// the bag.
+    pub fn exchange(&mut self, tiles: &[Letter]) -> Result<&TurnRecord, Error> {
+        self.check_not_over()?;
+        if tiles.is_empty() {
+            return Err(TurnError::NothingToExchange.into());
+        }
+        let ruleset = self.state.board.ruleset();
+        if !ruleset.can_exchange(self.in_bag()) {
+            return Err(TurnError::ExchangeNotAllowed {
+                in_bag: self.in_bag(),
+                needed: ruleset.rack_size,
+            }
+            .into());
+        }
+        let rack = &self.state.players[self.state.to_move].rack;
+        let kept = take_from_rack(ruleset, rack, tiles)?;
+        let mut thrown = leave_after(tiles, []);
+        for tile in &mut thrown {
+            if tile.is_blank() {
+                *tile = BLANK;
+            }
+        }
+
+        let before = self.state.clone();
+        let state = &mut self.state;
+        let player = state.to_move;
+        state.players[player].rack = kept.clone();
+        // The new tiles are drawn before the old ones go back in the bag.
+        let drawn = state.draw(player);
+        state.bag.extend(&thrown);
+        state.random.shuffle(&mut state.bag);
+        let end_adjustments = state.end_turn(0);
+        let record = TurnRecord {
+            player,
+            turn: Move::Exchange {
+                thrown,
+                kept,
+                equity: 0.0,
+            },
+            drawn,
+            end_adjustments,
+            challenge: None,
+        };
+        Ok(self.record(before, record))
+    }
+
+    /// Passes the turn of the player to move.
+    pub fn pass(&mut self) -> Result<&TurnRecord, Error> {
+        self.check_not_over()?;
+        let before = self.state.clone();
+        let player = self.state.to_move;
+        let end_adjustments = self.state.end_turn(0);
+        let record = TurnRecord {
+            player,
+            turn: Move::Pass { equity: 0.0 },
+            drawn: Vec::new(),
+            end_adjustments,
+            challenge: None,
+        };
+        Ok(self.record(before, record))
+    }
+
+    /// Makes `turn` for the player to move, such as one listed by
+    /// [`Solver::all_moves`].
+    pub fn apply(&mut self, solver: &Solver, turn: &Move) -> Result<&TurnRecord, Error> {
+        match turn {
+            Move::Place(legal_move) => self.play(
+                solver,
+                &legal_move.letters,
+                legal_move.row_index,
+                legal_move.column_index,
+                legal_move.across,
+            ),
+            Move::Exchange { thrown, .. } => self.exchange(thrown),
+            Move::Pass { .. } => self.pass(),
+        }
+    }
+
+    /// Challenges the last move, made by the previous player. A move forming
+    /// phonies is taken back, scoring nothing, and its player loses their
+    /// turn; the phonies are returned. A valid move stays, and under the
+    /// double rule the challenger loses their turn unless the move ended the
+    /// game. Under the void rule, there is nothing to challenge.
+    pub fn challenge(&mut self, solver: &Solver) -> Result<Vec<String>, Error> {
+        let (before, record) = match self.history.last() {
+            Some((before, record))
+                if self.challenge_rule != ChallengeRule::Void && record.challenge.is_none() =>
+            {
+                (before, record)
+            }
+            _ => return Err(TurnError::NothingToChallenge.into()),
+        };
+        let Move::Place(legal_move) = &record.turn else {
+            return Err(TurnError::NothingToChallenge.into());
+        };
+        let placement = solver.check(
+            &before.board,
+            &legal_move.letters,
+            legal_move.row_index,
+            legal_move.column_index,
+            legal_move.across,
+        )?;
+        if placement.phonies.is_empty() {
+            if self.challenge_rule == ChallengeRule::Double && !self.state.over {
+                let before = self.state.clone();
+                let player = self.state.to_move;
+                let end_adjustments = self.state.end_turn(0);
+                let record = TurnRecord {
+                    player,
+                    turn: Move::Pass { equity: 0.0 },
+                    drawn: Vec::new(),
+                    end_adjustments,
+                    challenge: Some(Challenge::Failed),
+                };
+                self.record(before, record);
+            }
+            return Ok(Vec::new());
+        }
+
+        // The game goes back to before the move, the tiles drawn going back
+        // in the bag, and the turn counts as scoreless.
+        let mut state = before.clone();
+        let player = record.player;
+        let legal_move = legal_move.clone();
+        state.random = self.state.random.clone();
+        state.random.shuffle(&mut state.bag);
+        let end_adjustments = state.end_turn(0);
+        let after = std::mem::replace(&mut self.state, state);
+        let record = TurnRecord {
+            player,
+            turn: Move::Place(legal_move),
+            drawn: Vec::new(),
+            end_adjustments,
+            challenge: Some(Challenge::Withdrawn(placement.phonies.clone())),
+        };
+        self.record(after, record);
+        Ok(placement.phonies)
+    }
+
+    /// Takes back the last turn played, returning `false` if there is none.
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            Some((before, record)) => {
+                let after = std::mem::replace(&mut self.state, before);
+                self.undone.push((after, record));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Plays again the last turn undone, draws included, returning `false`
+    /// if there is none. Any other turn played since forgets it.
+    pub fn redo(&mut self) -> bool {
+        match self.undone.pop() {
+            Some((after, record)) => {
+                let before = std::mem::replace(&mut self.state, after);
+                self.history.push((before, record));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Writes the turns played as a GCG record, see [`crate::gcg`], the
+    /// players going by the names given with [`Game::with_nicknames`].
+    pub fn gcg(&self) -> String {
+        let nicknames = &self.nicknames;
+        let alphabet = &self.state.board.ruleset().alphabet;
+        let mut record = String::from("#character-encoding UTF-8\n");
+        for (index, nickname) in nicknames.iter().enumerate() {
+            record.push_str(&format!("#player{} {nickname} {nickname}\n", index + 1));
+        }
+        for (index, (before, turn)) in self.history.iter().enumerate() {
+            let nickname = &nicknames[turn.player];
+            let rack = alphabet.write(&before.players[turn.player].rack);
+            let event = match &turn.turn {
+                Move::Place(legal_move) => {
+                    let letters = alphabet.parse(&legal_move.letters).unwrap_or_default();
+                    let word: String = alphabet
+                        .write_tiles(&letters)
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, tile)| {
+                            let (row_index, column_index) = match legal_move.across {
+                                true => (legal_move.row_index, legal_move.column_index + index),
+                                false => (legal_move.row_index + index, legal_move.column_index),
+                            };
+                            match before.board.tile(row_index, column_index) {
+                                0 => tile,
+                                _ => ".".to_string(),
+                            }
+                        })
+                        .collect();
+                    let coordinate = format_coordinate(
+                        legal_move.row_index,
+                        legal_move.column_index,
+                        legal_move.across,
+                    );
+                    format!("{coordinate} {word}")
+                }
+                Move::Exchange { thrown, .. } => format!("-{}", alphabet.write(thrown)),
+                Move::Pass { .. } => "-".to_string(),
+            };
+            let after = match self.history.get(index + 1) {
+                Some((after, _)) => after,
+                None => &self.state,
+            };
+            // A withdrawn move gives back the rack it was played from.
+            if let Some(Challenge::Withdrawn(_)) = turn.challenge {
+                record.push_str(&format!(
+                    ">{nickname}: {} -- -{} {}\n",
+                    alphabet.write(&after.players[turn.player].rack),
+                    turn.turn.score(),
+                    after.players[turn.player].score
+                ));
+                continue;
+            }
+            let total = before.players[turn.player].score + turn.turn.score() as i32;
+            record.push_str(&format!(
+                ">{nickname}: {rack} {event} +{} {total}\n",
+                turn.turn.score()
+            ));
+            // The end of the game, one line per rack counted.
+            for (player, adjustment) in turn.end_adjustments.iter().enumerate() {
+                if *adjustment == 0 {
+                    continue;
+                }
+                let tiles: Vec<Letter> = match *adjustment > 0 {
+                    true => (0..after.players.len())
+                        .filter(|other| *other != player)
+                        .flat_map(|other| after.players[other].rack.iter().copied())
+                        .collect(),
+                    false => after.players[player].rack.clone(),
+                };
+                record.push_str(&format!(
+                    ">{}: ({}) {adjustment:+} {}\n",
+                    nicknames[player],
+                    alphabet.write(&tiles),
+                    after.players[player].score
+                ));
+            }
+        }
+        record
+    }
+
+    fn check_not_over(&self) -> Result<(), TurnError> {
+        match self.state.over {
+            true => Err(TurnError::GameOver),
+            false => Ok(()),
+        }
+    }
+
+    fn record(&mut self, before: State, record: TurnRecord) -> &TurnRecord {
+        self.undone.clear();
+        self.history.push((before, record));
+        &self.history.last().unwrap().1
+    }
+}
+
+impl State {
+    /// Refills the rack of `player` from the bag, returning the tiles drawn.
+    fn draw(&mut self, player: usize) -> Vec<Letter> {
+        let rack_size = self.board.ruleset().rack_size;
+        let rack = &mut self.players[player].rack;
+        let count = rack_size.saturating_sub(rack.len()).min(self.bag.len());
+        let drawn = self.bag.split_off(self.bag.len() - count);
+        rack.extend(&drawn);
+        rack.sort_unstable();
+        drawn
+    }
+
+    fn rack_value(&self, player: usize) -> i32 {
+        self.players[player]
+            .rack
+            .iter()
+            .map(|tile| self.board.ruleset().letter_value(*tile) as i32)
+            .sum()
+    }
+
+    /// Ends the game for `player` going out, returning the adjustments.
+    fn go_out(&mut self, player: usize) -> Vec<i32> {
+        let mut adjustments: Vec<i32> = (0..self.players.len())
+            .map(|other| -self.rack_value(other))
+            .collect();
+        adjustments[player] = -adjustments.iter().sum::<i32>();
+        self.finish(adjustments)
+    }
+
+    /// Hands the turn over after a move scoring `score`, ending the game
+    /// after too many scoreless turns. Returns the end adjustments, if any.
+    fn end_turn(&mut self, score: u16) -> Vec<i32> {
+        self.scoreless_turns = match score {
+            0 => self.scoreless_turns + 1,
+            _ => 0,
+        };
+        if self.scoreless_turns >= SCORELESS_TURNS_LIMIT {
+            let adjustments = (0..self.players.len())
+                .map(|player| -self.rack_value(player))
+                .collect();
+            return self.finish(adjustments);
+        }
+        self.to_move = (self.to_move + 1) % self.players.len();
+        Vec::new()
+    }
+
+    fn finish(&mut self, adjustments: Vec<i32>) -> Vec<i32> {
+        for (player, adjustment) in self.players.iter_mut().zip(&adjustments) {
+            player.score += adjustment;
+        }
+        self.over = true;
+        adjustments
+    }
+}
+
+/// The tiles left on `rack` once `tiles` are taken from it, played blanks
+/// being taken as [`BLANK`]. Fails if the rack lacks any of them.
+fn take_from_rack(
+    ruleset: &Ruleset,
+    rack: &[Letter],
+    tiles: &[Letter],
+) -> Result<Vec<Letter>, TurnError> {
+    let mut left = rack.to_vec();
+    let mut missing = Vec::new();
+    for &tile in tiles {
+        let tile = if tile.is_blank() { BLANK } else { tile };
+        match left.iter().position(|rack_tile| *rack_tile == tile) {
+            Some(position) => {
+                left.swap_remove(position);
+            }
+            None => missing.push(tile),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(TurnError::NotOnRack(ruleset.alphabet.write(&missing)));
+    }
+    left.sort_unstable();
+    Ok(left)
+}
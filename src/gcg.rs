@@ -0,0 +1,531 @@
+//! Game records in the GCG format, as kept by tournament players, and their
+//! replay onto a [`Board`].
+//!
+//! A record is made of pragmas such as `#player1 nick Full Name` and of one
+//! line per event, for instance:
+//!
+//! ```text
+//! #player1 alice Alice
+//! #player2 bob Bob
+//! >alice: AEINRST 8D RETAINS +70 70
+//! >bob: ABCDEFG -ABC +0 0
+//! >alice: DEHILOU D8 .HOULD +24 94
+//! >alice: DEHILOU -- -24 70
+//! >bob: ADEFGXY - +0 0
+//! >alice: (DEF) +14 84
+//! ```
+//!
+//! Moves use the standard notation described in [`crate::notation`], except
+//! that `.` stands for a tile already on the board.
+
+use crate::alphabet::Alphabet;
+use crate::board::Board;
+use crate::error::Error;
+use crate::legal_moves::LegalMove;
+use crate::letter::Letter;
+use crate::lexicon::Lexicon;
+use crate::notation::parse_coordinate;
+use crate::position::Position;
+use crate::ruleset::Ruleset;
+use crate::score::word_value;
+use crate::solver::Solver;
+use std::fs;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// A player named by the `#player1` and `#player2` pragmas.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Player {
+    /// The name used on the event lines.
+    pub nickname: String,
+    pub name: String,
+    /// The tiles left on the rack when the record ends, from `#rack1` or
+    /// `#rack2`.
+    pub final_rack: Option<String>,
+}
+
+/// What happened during a turn.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Tiles placed on the board. `word` is written as in the record, with
+    /// `.` for the tiles already on the board.
+    Play {
+        row_index: usize,
+        column_index: usize,
+        across: bool,
+        word: String,
+    },
+    Pass,
+    /// The tiles thrown back in the bag, or their number when they are
+    /// hidden.
+    Exchange(String),
+    /// The previous move of the player was challenged off the board.
+    Withdrawn,
+    /// Points given for a move challenged without success.
+    ChallengeBonus,
+    /// Points lost for going over time.
+    TimePenalty,
+    /// Points gained for the tiles left on an opponent's rack, or lost for
+    /// the tiles left on one's own, when the game ends.
+    EndRack(String),
+}
+
+/// One event line of a record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Turn {
+    /// The index of the player in [`Game::players`].
+    pub player: usize,
+    /// The rack before the turn, when the record gives it.
+    pub rack: Option<String>,
+    pub action: Action,
+    /// The points scored, negative for withdrawn moves and penalties.
+    pub score: i32,
+    /// The score of the player after the turn.
+    pub total: i32,
+    /// The line of the record the turn comes from.
+    pub line: usize,
+}
+
+/// A game read from a GCG record.
+#[derive(Clone, Debug, Default)]
+pub struct Game {
+    pub players: Vec<Player>,
+    pub turns: Vec<Turn>,
+}
+
+fn invalid(line: usize, message: impl Into<String>) -> Error {
+    Error::InvalidGame {
+        line,
+        message: message.into(),
+    }
+}
+
+fn parse_score(line: usize, text: &str) -> Result<i32, Error> {
+    text.strip_prefix('+')
+        .unwrap_or(text)
+        .parse()
+        .map_err(|_| invalid(line, format!("'{text}' is not a score")))
+}
+
+/// Checks that a rack or a list of tiles only holds letters and blanks.
+/// Whether they are letters of the alphabet is checked on replay.
+fn parse_tiles(line: usize, text: &str) -> Result<String, Error> {
+    if text
+        .chars()
+        .all(|tile| tile == '?' || tile == '·' || tile.is_uppercase())
+    {
+        Ok(text.to_string())
+    } else {
+        Err(invalid(line, format!("'{text}' is not a set of tiles")))
+    }
+}
+
+impl Game {
+    /// Reads a GCG file.
+    pub fn load(path: &str) -> Result<Game, Error> {
+        let text = fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        text.parse()
+    }
+
+    fn player_index(&mut self, nickname: &str) -> usize {
+        match self
+            .players
+            .iter()
+            .position(|player| player.nickname == nickname)
+        {
+            Some(index) => index,
+            None => {
+                self.players.push(Player {
+                    nickname: nickname.to_string(),
+                    name: nickname.to_string(),
+                    final_rack: None,
+                });
+                self.players.len() - 1
+            }
+        }
+    }
+
+    fn parse_pragma(&mut self, line: usize, pragma: &str) -> Result<(), Error> {
+        let (keyword, rest) = pragma.split_once(' ').unwrap_or((pragma, ""));
+        let number = match keyword {
+            "player1" | "rack1" => 0,
+            "player2" | "rack2" => 1,
+            // Titles, notes, lexicon names and the like do not matter here.
+            _ => return Ok(()),
+        };
+        if self.players.len() < number {
+            return Err(invalid(line, "#player2 comes before #player1"));
+        }
+        if keyword.starts_with("player") {
+            let (nickname, name) = rest.trim().split_once(' ').unwrap_or((rest.trim(), ""));
+            if nickname.is_empty() {
+                return Err(invalid(line, "a player needs a nickname"));
+            }
+            let player = Player {
+                nickname: nickname.to_string(),
+                name: name.trim().to_string(),
+                final_rack: None,
+            };
+            if number == self.players.len() {
+                self.players.push(player);
+            } else {
+                self.players[number] = player;
+            }
+        } else {
+            let rack = parse_tiles(line, rest.trim())?;
+            match self.players.get_mut(number) {
+                Some(player) => player.final_rack = Some(rack),
+                None => return Err(invalid(line, "a rack is given for an unknown player")),
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_turn(&mut self, line: usize, event: &str) -> Result<(), Error> {
+        let (nickname, rest) = event
+            .split_once(':')
+            .ok_or_else(|| invalid(line, "expected '>nickname: ...'"))?;
+        let player = self.player_index(nickname.trim());
+        let tokens: Vec<&str> = rest.split_whitespace().collect();
+        let [middle @ .., score, total] = tokens.as_slice() else {
+            return Err(invalid(line, "expected a score and a total"));
+        };
+        let score = parse_score(line, score)?;
+        let total = parse_score(line, total)?;
+        let is_marker = |token: &str| token.starts_with('-') || token.starts_with('(');
+        let (rack, middle) = match middle {
+            [rack, coordinate, word] => (Some(*rack), vec![*coordinate, *word]),
+            [rack, marker] if is_marker(marker) => (Some(*rack), vec![*marker]),
+            [rack] if !is_marker(rack) => {
+                return Err(invalid(line, format!("expected a move after '{rack}'")))
+            }
+            _ => (None, middle.to_vec()),
+        };
+        let rack = rack.map(|rack| parse_tiles(line, rack)).transpose()?;
+        let action = match middle.as_slice() {
+            [coordinate, word] => {
+                let (row_index, column_index, across) = parse_coordinate(coordinate)
+                    .ok_or_else(|| invalid(line, format!("'{coordinate}' is not a square")))?;
+                if !word
+                    .chars()
+                    .all(|letter| letter == '.' || letter == '·' || letter.is_alphabetic())
+                {
+                    return Err(invalid(line, format!("'{word}' is not a word")));
+                }
+                Action::Play {
+                    row_index,
+                    column_index,
+                    across,
+                    word: word.to_string(),
+                }
+            }
+            ["-"] => Action::Pass,
+            ["--"] => Action::Withdrawn,
+            ["(challenge)"] => Action::ChallengeBonus,
+            ["(time)"] => Action::TimePenalty,
+            [marker] => match (marker.strip_prefix('-'), marker.strip_prefix('(')) {
+                (Some(tiles), _) if tiles.chars().all(|tile| tile.is_ascii_digit()) => {
+                    Action::Exchange(tiles.to_string())
+                }
+                (Some(tiles), _) => Action::Exchange(parse_tiles(line, tiles)?),
+                (_, Some(tiles)) => match tiles.strip_suffix(')') {
+                    Some(tiles) => Action::EndRack(parse_tiles(line, tiles)?),
+                    None => return Err(invalid(line, format!("unclosed '{marker}'"))),
+                },
+                _ => return Err(invalid(line, format!("unknown event '{marker}'"))),
+            },
+            _ => return Err(invalid(line, "unknown event")),
+        };
+        self.turns.push(Turn {
+            player,
+            rack,
+            action,
+            score,
+            total,
+            line,
+        });
+        Ok(())
+    }
+
+    /// Replays the game onto a board played under `ruleset` and returns the
+    /// position before each turn, followed by the final position. Every
+    /// position holds the rack of the player about to move, when the record
+    /// gives it, and the scores so far.
+    ///
+    /// Placements are checked against the rules, but words missing from
+    /// `lexicon` are still played, as the record may withdraw them on the
+    /// next turn. The points of every turn are checked as well: plays must
+    /// score what the board gives, passes and exchanges nothing, withdrawn
+    /// moves must lose what they scored and racks counted at the end must be
+    /// worth once or twice the points given. Tiles played or exchanged must
+    /// be on the rack, when it is recorded, and every total must add up.
+    pub fn replay(&self, lexicon: &Lexicon, ruleset: Arc<Ruleset>) -> Result<Vec<Position>, Error> {
+        let mut positions: Vec<Position> = Vec::with_capacity(self.turns.len() + 1);
+        let mut position = Position {
+            scores: vec![0; self.players.len()],
+            ..Position::new(Board::with_ruleset(ruleset.clone()))
+        };
+        // The board before the last move of each player and what the move
+        // scored, restored when it is withdrawn.
+        let mut before_last_play: Vec<Option<(Board, i32)>> = vec![None; self.players.len()];
+        let alphabet = &ruleset.alphabet;
+        let parse = |line: usize, tiles: &str| {
+            alphabet
+                .parse(tiles)
+                .map_err(|error| invalid(line, error.to_string()))
+        };
+        for turn in &self.turns {
+            position.rack = turn.rack.clone();
+            positions.push(position.clone());
+            let rack = turn
+                .rack
+                .as_ref()
+                .map(|rack| parse(turn.line, rack))
+                .transpose()?;
+            let take = |tiles: &[Letter]| match &rack {
+                Some(rack) => take_from_rack(turn.line, alphabet, rack, tiles),
+                None => Ok(()),
+            };
+            let expected = match &turn.action {
+                Action::Play {
+                    row_index,
+                    column_index,
+                    across,
+                    word,
+                } => {
+                    let word = fill_word(&position.board, *row_index, *column_index, *across, word)
+                        .map_err(|message| invalid(turn.line, message))?;
+                    let placement = position
+                        .board
+                        .check_move(lexicon, &word, *row_index, *column_index, *across)
+                        .map_err(|error| invalid(turn.line, error.to_string()))?;
+                    take(&placement.rack_tiles)?;
+                    let score = placement.score as i32;
+                    before_last_play[turn.player] = Some((position.board.clone(), score));
+                    position
+                        .board
+                        .play(&word, *row_index, *column_index, *across)?;
+                    Some(score)
+                }
+                Action::Withdrawn => match before_last_play[turn.player].take() {
+                    Some((board, score)) => {
+                        position.board = board;
+                        Some(-score)
+                    }
+                    None => return Err(invalid(turn.line, "there is no move to withdraw")),
+                },
+                Action::Pass => Some(0),
+                Action::Exchange(tiles) => {
+                    if !tiles.chars().all(|character| character.is_ascii_digit()) {
+                        take(&parse(turn.line, tiles)?)?;
+                    }
+                    Some(0)
+                }
+                Action::EndRack(tiles) => {
+                    let value = word_value(&ruleset, &parse(turn.line, tiles)?) as i32;
+                    if ![value, 2 * value, -value].contains(&turn.score) {
+                        return Err(invalid(
+                            turn.line,
+                            format!(
+                                "the tiles ({tiles}) are worth {value} points, not {}",
+                                turn.score
+                            ),
+                        ));
+                    }
+                    None
+                }
+                Action::ChallengeBonus | Action::TimePenalty => None,
+            };
+            if let Some(expected) = expected.filter(|expected| *expected != turn.score) {
+                return Err(invalid(
+                    turn.line,
+                    format!("the turn scores {expected} points, not {}", turn.score),
+                ));
+            }
+            let total = position.scores[turn.player] + turn.score;
+            if total != turn.total {
+                return Err(invalid(
+                    turn.line,
+                    format!("the total comes to {total} points, not {}", turn.total),
+                ));
+            }
+            position.scores[turn.player] = total;
+        }
+        position.rack = None;
+        positions.push(position);
+        Ok(positions)
+    }
+}
+
+/// The moves available on a recorded turn, compared with the one played.
+#[derive(Clone, Debug)]
+pub struct Analysis {
+    /// Every legal move for the rack, best first.
+    pub moves: Vec<LegalMove>,
+    /// The index of the played move in `moves`, `None` when it is not a
+    /// legal move, such as a phony.
+    pub played: Option<usize>,
+}
+
+impl Game {
+    /// Lists the moves available on a turn and finds the played one among
+    /// them. `position` is the position before the turn, as returned by
+    /// [`Game::replay`]. Returns `None` for turns that are not plays or
+    /// whose rack is not recorded.
+    pub fn analyse(
+        &self,
+        solver: &Solver,
+        position: &Position,
+        turn_index: usize,
+    ) -> Result<Option<Analysis>, Error> {
+        let turn = &self.turns[turn_index];
+        let (
+            Action::Play {
+                row_index,
+                column_index,
+                across,
+                word,
+            },
+            Some(rack),
+        ) = (&turn.action, &turn.rack)
+        else {
+            return Ok(None);
+        };
+        let word = fill_word(&position.board, *row_index, *column_index, *across, word)
+            .map_err(|message| invalid(turn.line, message))?;
+        let moves = solver
+            .moves(&position.board, rack)
+            .map_err(|error| invalid(turn.line, error.to_string()))?;
+        // A lone tile forming words both ways is listed once, so the played
+        // move is found by the tiles it places rather than by its notation.
+        let letters = position
+            .board
+            .ruleset()
+            .alphabet
+            .parse(&word)
+            .map_err(|error| invalid(turn.line, error.to_string()))?;
+        let mut placed = placed_tiles(
+            &position.board,
+            *row_index,
+            *column_index,
+            *across,
+            &letters,
+        );
+        placed.sort_unstable();
+        let played = moves.iter().position(|legal_move| {
+            let mut tiles = legal_move.placed_tiles.clone();
+            tiles.sort_unstable();
+            tiles == placed
+        });
+        Ok(Some(Analysis { moves, played }))
+    }
+}
+
+/// The tiles of `word` placed on empty squares, as
+/// `(row_index, column_index, tile)`.
+fn placed_tiles(
+    board: &Board,
+    row_index: usize,
+    column_index: usize,
+    across: bool,
+    word: &[Letter],
+) -> Vec<(usize, usize, Letter)> {
+    word.iter()
+        .enumerate()
+        .map(|(index, letter)| match across {
+            true => (row_index, column_index + index, *letter),
+            false => (row_index + index, column_index, *letter),
+        })
+        .filter(|(row, column, _)| board.tile(*row, *column) == 0)
+        .collect()
+}
+
+/// Checks that `rack` holds `tiles`.
+fn take_from_rack(
+    line: usize,
+    alphabet: &Alphabet,
+    rack: &[Letter],
+    tiles: &[Letter],
+) -> Result<(), Error> {
+    let mut left = rack.to_vec();
+    for tile in tiles {
+        match left.iter().position(|letter| letter == tile) {
+            Some(index) => {
+                left.swap_remove(index);
+            }
+            None => {
+                return Err(invalid(
+                    line,
+                    format!(
+                        "the rack {} does not hold the tiles {}",
+                        alphabet.write(rack),
+                        alphabet.write(tiles)
+                    ),
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the `.` of a recorded word by the tiles already on the board.
+fn fill_word(
+    board: &Board,
+    row_index: usize,
+    column_index: usize,
+    across: bool,
+    word: &str,
+) -> Result<String, String> {
+    let alphabet = &board.ruleset().alphabet;
+    let mut filled = Vec::new();
+    let mut rest = word;
+    while let Some(character) = rest.chars().next() {
+        let index = filled.len();
+        let (row, column) = if across {
+            (row_index, column_index + index)
+        } else {
+            (row_index + index, column_index)
+        };
+        let (tile, length) = if character == '.' {
+            match (row < board.size() && column < board.size()).then(|| board.tile(row, column)) {
+                Some(0) | None => {
+                    return Err(format!(
+                        "'{word}' goes through an empty square on row {} column {}",
+                        row + 1,
+                        column + 1
+                    ))
+                }
+                Some(tile) => (tile, 1),
+            }
+        } else {
+            alphabet
+                .read_tile(rest)
+                .ok_or_else(|| format!("'{word}' holds '{character}', which is not a letter"))?
+        };
+        filled.push(tile);
+        rest = &rest[length..];
+    }
+    Ok(alphabet.write(&filled))
+}
+
+impl FromStr for Game {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut game = Game::default();
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if let Some(pragma) = line.strip_prefix('#') {
+                game.parse_pragma(line_number, pragma)?;
+            } else if let Some(event) = line.strip_prefix('>') {
+                game.parse_turn(line_number, event)?;
+            }
+            // Any other line continues a note and is ignored.
+        }
+        Ok(game)
+    }
+}
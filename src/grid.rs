@@ -0,0 +1,68 @@
+//! Square grids sized at runtime, for boards of any size.
+
+use std::ops::{Index, IndexMut};
+
+/// A square grid of cells stored row after row, indexed by
+/// `(row_index, column_index)`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    size: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    /// A `size` by `size` grid filled with `value`.
+    pub fn new(size: usize, value: T) -> Grid<T> {
+        Grid {
+            size,
+            cells: vec![value; size * size],
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    /// Builds a grid from its rows, `None` if they do not form a square.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Option<Grid<T>> {
+        let size = rows.len();
+        if rows.iter().any(|row| row.len() != size) {
+            return None;
+        }
+        Some(Grid {
+            size,
+            cells: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    /// The number of rows, which is also the number of columns.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn row(&self, row_index: usize) -> &[T] {
+        &self.cells[row_index * self.size..(row_index + 1) * self.size]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.size.max(1))
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (row_index, column_index): (usize, usize)) -> &T {
+        debug_assert!(column_index < self.size);
+        &self.cells[row_index * self.size + column_index]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (row_index, column_index): (usize, usize)) -> &mut T {
+        debug_assert!(column_index < self.size);
+        &mut self.cells[row_index * self.size + column_index]
+    }
+}
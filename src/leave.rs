@@ -0,0 +1,158 @@
+//! What the tiles kept on the rack after a move are worth, so that moves can
+//! be ranked by equity, their score plus the value of their leave, rather
+//! than by score alone.
+//!
+//! Leave values are read from a file of one leave per line, written in the
+//! alphabet of the ruleset with `?` for a blank, followed by its value in
+//! points, separated by a comma or spaces:
+//!
+//! ```text
+//! # Comments start with '#'.
+//! ?S 25.3
+//! VVW,-22.1
+//! ```
+//!
+//! Leaves missing from the table, or every leave when no table is given, are
+//! valued by a heuristic rewarding blanks and `S` and penalising heavy tiles,
+//! duplicates and racks short of vowels or consonants.
+
+use crate::alphabet::Alphabet;
+use crate::error::Error;
+use crate::letter::{Blank, Letter, BLANK};
+use crate::ruleset::Ruleset;
+use std::collections::HashMap;
+use std::fs;
+
+/// Letters counted as vowels by the heuristic, in any alphabet.
+const VOWELS: &str = "AEIOUÀÁÂÄÈÉÊËÌÍÎÏÒÓÔÖÙÚÛÜ";
+
+/// Values of rack leaves, from a table and the built-in heuristic.
+#[derive(Clone, Debug, Default)]
+pub struct Leaves {
+    /// Leave values by sorted tiles.
+    table: HashMap<Vec<Letter>, f64>,
+}
+
+impl Leaves {
+    /// Values every leave with the heuristic.
+    pub fn heuristic() -> Leaves {
+        Leaves::default()
+    }
+
+    /// Reads a table of leave values written in `alphabet`.
+    pub fn load(path: &str, alphabet: &Alphabet) -> Result<Leaves, Error> {
+        let text = fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        Leaves::parse(&text, alphabet)
+    }
+
+    /// Reads a table of leave values from the text of a leave file.
+    pub fn parse(text: &str, alphabet: &Alphabet) -> Result<Leaves, Error> {
+        let invalid = |line: usize, message: String| Error::InvalidLeaves { line, message };
+        let mut table = HashMap::new();
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let [tiles, value] = line
+                .split(|character: char| character == ',' || character.is_whitespace())
+                .filter(|field| !field.is_empty())
+                .collect::<Vec<_>>()[..]
+            else {
+                return Err(invalid(
+                    line_number,
+                    "expected a leave followed by its value".to_string(),
+                ));
+            };
+            let mut tiles = alphabet
+                .parse(tiles)
+                .ok()
+                .filter(|tiles| tiles.iter().all(|tile| *tile == BLANK || !tile.is_blank()))
+                .ok_or_else(|| {
+                    invalid(
+                        line_number,
+                        format!("'{tiles}' is not a rack of the alphabet"),
+                    )
+                })?;
+            let value = value
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .ok_or_else(|| invalid(line_number, format!("'{value}' is not a number")))?;
+            tiles.sort_unstable();
+            table.insert(tiles, value);
+        }
+        Ok(Leaves { table })
+    }
+
+    /// The number of leaves in the table.
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    /// What keeping `leave` is worth in points, blanks being [`BLANK`].
+    pub fn value(&self, ruleset: &Ruleset, leave: &[Letter]) -> f64 {
+        if leave.is_empty() {
+            return 0.0;
+        }
+        let mut tiles = leave.to_vec();
+        tiles.sort_unstable();
+        match self.table.get(&tiles) {
+            Some(value) => *value,
+            None => heuristic_value(ruleset, &tiles),
+        }
+    }
+}
+
+/// A rough value for sorted `tiles`: blanks and `S` are worth keeping, heavy
+/// tiles are hard to play, and duplicates and unbalanced racks draw poorly.
+fn heuristic_value(ruleset: &Ruleset, tiles: &[Letter]) -> f64 {
+    let alphabet = &ruleset.alphabet;
+    let mut value = 0.0;
+    let mut vowels = 0;
+    let mut consonants = 0;
+    for (index, tile) in tiles.iter().enumerate() {
+        if *tile == BLANK {
+            value += 20.0;
+            continue;
+        }
+        let token = alphabet.token(*tile);
+        value += match token {
+            "S" => 8.0,
+            _ => 1.0 - 0.6 * (ruleset.letter_value(*tile) as f64 - 1.0),
+        };
+        if index > 0 && tiles[index - 1] == *tile {
+            value -= 3.0;
+        }
+        if token.chars().all(|character| VOWELS.contains(character)) {
+            vowels += 1;
+        } else {
+            consonants += 1;
+        }
+    }
+    // Draws fill the rack best when about two tiles in five are vowels.
+    let imbalance = (vowels as f64 - 0.4 * (vowels + consonants) as f64).abs();
+    value - 3.0 * (imbalance - 0.5).max(0.0)
+}
+
+/// The tiles left on `rack` once `placed` are played, blanks standing for a
+/// letter being taken as [`BLANK`].
+pub fn leave_after(rack: &[Letter], placed: impl IntoIterator<Item = Letter>) -> Vec<Letter> {
+    let mut leave = rack.to_vec();
+    for tile in placed {
+        let tile = if tile.is_blank() { BLANK } else { tile };
+        if let Some(position) = leave.iter().position(|rack_tile| *rack_tile == tile) {
+            leave.swap_remove(position);
+        }
+    }
+    leave.sort_unstable();
+    leave
+}
@@ -1,29 +1,57 @@
+//! Move generation, after Appel and Jacobson's algorithm.
+
+use crate::alphabet::Alphabet;
 use crate::board::Board;
-use crate::letter::{Letter, ToChar};
-use crate::lexicon::{Lexicon, Node};
-use crate::score::{calculate_score, word_value};
+use crate::error::Error;
+use crate::grid::Grid;
+use crate::letter::{letters_in, Blank, Letter, LetterSet, ALL_LETTERS, BLANK};
+use crate::lexicon::{Lexicon, NodeId, WordGraph, SEPARATOR};
+use crate::score::score_move;
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+/// A word that can be played, with the square of its first letter.
+#[derive(Clone, Debug, PartialEq)]
 pub struct LegalMove {
     pub row_index: usize,
     pub column_index: usize,
     pub across: bool,
+    /// The whole word, tiles already on the board included, blanks in
+    /// lowercase.
     pub letters: String,
     pub score: u16,
+    /// The tiles placed from the rack and their squares, in order along the
+    /// word. Two moves placing the same tiles are the same move.
+    pub placed_tiles: Vec<(usize, usize, Letter)>,
+    /// The score plus the value of the tiles left on the rack, see
+    /// [`Solver::moves`](crate::Solver::moves). Equal to the score until a
+    /// leave is valued.
+    pub equity: f64,
 }
 
 impl LegalMove {
     fn new(
         board: &Board,
-        value_set: &[[u16; 15]; 15],
         mut row_index: usize,
         mut column_index: usize,
         across: bool,
-        letters: String,
+        letters: &[Letter],
         bingo: bool,
     ) -> Self {
-        let score = calculate_score(board, value_set, &letters, row_index, column_index, bingo);
+        let score = score_move(board, letters, row_index, column_index, bingo).total();
+        let placed_tiles = letters
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| board.primary[(row_index, column_index + index)] == 0)
+            .map(|(index, letter)| {
+                if board.across {
+                    (row_index, column_index + index, *letter)
+                } else {
+                    (column_index + index, row_index, *letter)
+                }
+            })
+            .collect();
+        let letters = board.ruleset().alphabet.write(letters);
         if !board.across {
             (row_index, column_index) = (column_index, row_index);
         }
@@ -33,6 +61,54 @@ impl LegalMove {
             across,
             letters,
             score,
+            placed_tiles,
+            equity: score as f64,
+        }
+    }
+}
+
+/// Anything a player can do on their turn.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Move {
+    /// Tiles placed on the board.
+    Place(LegalMove),
+    /// Tiles thrown back in the bag for as many new ones, scoring nothing.
+    Exchange {
+        /// The tiles thrown back, sorted, [`BLANK`] for a blank.
+        thrown: Vec<Letter>,
+        /// The tiles kept, sorted.
+        kept: Vec<Letter>,
+        /// The value of the tiles kept.
+        equity: f64,
+    },
+    /// Nothing done, the whole rack being kept.
+    Pass {
+        /// The value of the rack.
+        equity: f64,
+    },
+}
+
+impl Move {
+    pub fn score(&self) -> u16 {
+        match self {
+            Move::Place(legal_move) => legal_move.score,
+            Move::Exchange { .. } | Move::Pass { .. } => 0,
+        }
+    }
+
+    /// The score plus the value of the tiles kept.
+    pub fn equity(&self) -> f64 {
+        match self {
+            Move::Place(legal_move) => legal_move.equity,
+            Move::Exchange { equity, .. } | Move::Pass { equity } => *equity,
+        }
+    }
+
+    /// The tiles placed, for a move placing any.
+    pub fn placement(&self) -> Option<&LegalMove> {
+        match self {
+            Move::Place(legal_move) => Some(legal_move),
+            Move::Exchange { .. } | Move::Pass { .. } => None,
         }
     }
 }
@@ -46,6 +122,7 @@ fn remove_letter_from_rack(rack: &mut HashMap<Letter, u8>, letter: Letter) {
     }
 }
 
+/// Adds one `letter` tile to a rack.
 pub fn add_letter_to_rack(rack: &mut HashMap<Letter, u8>, letter: Letter) {
     match rack.entry(letter) {
         Entry::Occupied(mut entry) => *entry.get_mut() += 1,
@@ -55,28 +132,76 @@ pub fn add_letter_to_rack(rack: &mut HashMap<Letter, u8>, letter: Letter) {
     }
 }
 
-fn calculate_anchors(board: &Board) -> [u16; 15] {
-    let mut anchors = [0; 15];
-    for (row_index, row) in board.primary.iter().enumerate() {
+/// Reads a rack written as letters of `alphabet`, with `?` for a blank.
+pub fn parse_rack(tiles: &str, alphabet: &Alphabet) -> Result<HashMap<Letter, u8>, Error> {
+    rack_from_tiles(&alphabet.parse(tiles)?, alphabet)
+}
+
+/// Builds a rack from tiles of `alphabet`, [`BLANK`] standing for a blank.
+pub fn rack_from_tiles(
+    tiles: &[Letter],
+    alphabet: &Alphabet,
+) -> Result<HashMap<Letter, u8>, Error> {
+    let mut rack = HashMap::new();
+    for tile in tiles {
+        add_letter_to_rack(&mut rack, *tile);
+    }
+    validate_rack(&rack, alphabet)?;
+    Ok(rack)
+}
+
+fn validate_rack(rack: &HashMap<Letter, u8>, alphabet: &Alphabet) -> Result<(), Error> {
+    match rack.keys().find(|letter| {
+        **letter == 0
+            || (letter.is_blank() && **letter != BLANK)
+            || letter.unblanked() as usize > alphabet.letter_count()
+    }) {
+        Some(letter) => Err(Error::InvalidRackTile(alphabet.token(*letter).to_string())),
+        None => Ok(()),
+    }
+}
+
+/// Lists the rack tiles that can be played as `letter`: the letter itself
+/// and a blank, whichever are available.
+fn rack_tiles_for(rack: &HashMap<Letter, u8>, letter: Letter) -> [Option<Letter>; 2] {
+    [
+        rack.contains_key(&letter).then_some(letter),
+        rack.contains_key(&BLANK).then_some(BLANK),
+    ]
+}
+
+/// The tile that ends up on the board when `rack_tile` is played as `letter`.
+fn played_tile(rack_tile: Letter, letter: Letter) -> Letter {
+    if rack_tile == BLANK {
+        letter | BLANK
+    } else {
+        letter
+    }
+}
+
+fn calculate_anchors(board: &Board) -> Grid<bool> {
+    let last = board.size() - 1;
+    let mut anchors = Grid::new(board.size(), false);
+    for (row_index, row) in board.primary.rows().enumerate() {
         for (column_index, column) in row.iter().enumerate() {
             if column != &0 {
                 if column_index > 0 && row[column_index - 1] == 0 {
-                    anchors[row_index] |= 1 << column_index - 1;
+                    anchors[(row_index, column_index - 1)] = true;
                 }
-                if column_index < 14 && row[column_index + 1] == 0 {
-                    anchors[row_index] |= 1 << column_index + 1;
+                if column_index < last && row[column_index + 1] == 0 {
+                    anchors[(row_index, column_index + 1)] = true;
                 }
             }
         }
     }
-    for (column_index, column) in board.secondary.iter().enumerate() {
+    for (column_index, column) in board.secondary.rows().enumerate() {
         for (row_index, row) in column.iter().enumerate() {
             if row != &0 {
                 if row_index > 0 && column[row_index - 1] == 0 {
-                    anchors[row_index - 1] |= 1 << column_index;
+                    anchors[(row_index - 1, column_index)] = true;
                 }
-                if row_index < 14 && column[row_index + 1] == 0 {
-                    anchors[row_index + 1] |= 1 << column_index;
+                if row_index < last && column[row_index + 1] == 0 {
+                    anchors[(row_index + 1, column_index)] = true;
                 }
             }
         }
@@ -84,126 +209,127 @@ fn calculate_anchors(board: &Board) -> [u16; 15] {
     anchors
 }
 
-fn calculate_cross_check_sets_and_value_set(
-    lexicon: &Lexicon,
-    board: &Board,
-) -> ([[u32; 15]; 15], [[u16; 15]; 15]) {
-    let mut cross_check_sets = [[67108863; 15]; 15];
-    let mut value_set = [[0; 15]; 15];
+/// The letters that may be placed on each square of `board.primary` given
+/// the words they would form with the tiles above and below.
+fn calculate_cross_check_sets(lexicon: &Lexicon, board: &Board) -> Grid<LetterSet> {
+    let last = board.size() - 1;
+    let mut cross_check_sets = Grid::new(board.size(), ALL_LETTERS);
 
-    for (column_index, column) in board.secondary.iter().enumerate() {
+    for (column_index, column) in board.secondary.rows().enumerate() {
         for (row_index, letter) in column.iter().enumerate() {
             if letter != &0 {
                 if row_index > 0 && column[row_index - 1] == 0 {
-                    (
-                        cross_check_sets[row_index - 1][column_index],
-                        value_set[row_index - 1][column_index],
-                    ) = calculate_letter_set_and_score(lexicon, column, row_index - 1);
+                    cross_check_sets[(row_index - 1, column_index)] =
+                        calculate_letter_set(lexicon, column, row_index - 1);
                 }
-                if row_index < 14 && column[row_index + 1] == 0 {
-                    (
-                        cross_check_sets[row_index + 1][column_index],
-                        value_set[row_index + 1][column_index],
-                    ) = calculate_letter_set_and_score(lexicon, column, row_index + 1);
+                if row_index < last && column[row_index + 1] == 0 {
+                    cross_check_sets[(row_index + 1, column_index)] =
+                        calculate_letter_set(lexicon, column, row_index + 1);
                 }
             }
         }
     }
 
-    (cross_check_sets, value_set)
+    cross_check_sets
 }
 
-fn calculate_letter_set_and_score(
+fn calculate_letter_set(
     lexicon: &Lexicon,
-    column: &[Letter; 15],
+    column: &[Letter],
     anchor_row_index: usize,
-) -> (u32, u16) {
+) -> LetterSet {
     let mut letter_set = 0;
     let mut current_row_index = anchor_row_index;
-    let mut prefix = String::new();
-    let mut suffix = String::new();
-    loop {
-        if current_row_index > 0 && column[current_row_index - 1] != 0 {
-            current_row_index -= 1;
-            prefix.insert(0, (column[current_row_index] as Letter).to_char());
-        } else {
-            break;
-        }
+    while current_row_index > 0 && column[current_row_index - 1] != 0 {
+        current_row_index -= 1;
     }
+    let prefix = &column[current_row_index..anchor_row_index];
     let mut current_row_index = anchor_row_index;
-    loop {
-        if current_row_index < 14 && column[current_row_index + 1] != 0 {
-            current_row_index += 1;
-            suffix.push((column[current_row_index] as Letter).to_char());
-        } else {
-            break;
-        }
+    while current_row_index + 1 < column.len() && column[current_row_index + 1] != 0 {
+        current_row_index += 1;
     }
-    if let Some(node) = lexicon.root.get_node(&prefix) {
-        for (edge, current_node) in node.children.iter() {
-            if let Some(final_node) = current_node.get_node(&suffix) {
-                if final_node.is_terminal {
-                    letter_set |= 1 << edge - 1;
+    let suffix = &column[anchor_row_index + 1..=current_row_index];
+    let dawg = &lexicon.dawg;
+    if let Some(node) = dawg.get_node(prefix) {
+        for edge in letters_in(dawg.edges(node)) {
+            let current_node = dawg.child(node, edge).unwrap();
+            if let Some(final_node) = dawg.walk(current_node, suffix) {
+                if dawg.is_terminal(final_node) {
+                    letter_set |= 1 << edge;
                 }
             }
         }
     }
-    let full_word = format!("{} {}", prefix, suffix);
-    let score = word_value(&full_word);
+    letter_set
+}
 
-    (letter_set, score)
+/// State shared by the recursive search along the rows of `board.primary`.
+struct MoveGenerator<'a, G: WordGraph> {
+    graph: &'a G,
+    board: &'a Board,
+    anchors: Grid<bool>,
+    cross_check_sets: Grid<LetterSet>,
+    /// The number of tiles on the rack before the move.
+    rack_tile_count: usize,
+    legal_moves: Vec<LegalMove>,
 }
 
-fn extend_right(
-    board: &Board,
-    cross_check_sets: &[[u32; 15]; 15],
-    value_set: &[[u16; 15]; 15],
-    legal_moves: &mut Vec<LegalMove>,
-    row_index: usize,
-    column_index: usize,
-    node: &Node,
-    rack: &mut HashMap<Letter, u8>,
-    partial_word: &mut String,
-    possible: bool,
-) {
-    let current_column_index = column_index + partial_word.len();
-    if current_column_index >= 15 {
-        if node.is_terminal && possible {
-            legal_moves.push(LegalMove::new(
-                board,
-                value_set,
-                row_index,
-                column_index,
-                board.across.clone(),
-                partial_word.clone(),
-                rack.is_empty(),
-            ));
+impl<G: WordGraph> MoveGenerator<'_, G> {
+    fn record(
+        &mut self,
+        row_index: usize,
+        column_index: usize,
+        rack: &HashMap<Letter, u8>,
+        partial_word: &[Letter],
+    ) {
+        let placed_tile_count =
+            self.rack_tile_count - rack.values().map(|count| *count as usize).sum::<usize>();
+        self.legal_moves.push(LegalMove::new(
+            self.board,
+            row_index,
+            column_index,
+            self.board.across,
+            partial_word,
+            placed_tile_count == self.board.ruleset().rack_size,
+        ));
+    }
+
+    /// The letters that may be placed on an empty square from `node`.
+    fn playable_letters(&self, node: NodeId, row_index: usize, column_index: usize) -> LetterSet {
+        self.graph.edges(node) & self.cross_check_sets[(row_index, column_index)]
+    }
+
+    fn is_anchor(&self, row_index: usize, column_index: usize) -> bool {
+        self.anchors[(row_index, column_index)]
+    }
+
+    fn extend_right(
+        &mut self,
+        row_index: usize,
+        column_index: usize,
+        node: NodeId,
+        rack: &mut HashMap<Letter, u8>,
+        partial_word: &mut Vec<Letter>,
+        possible: bool,
+    ) {
+        let current_column_index = column_index + partial_word.len();
+        if current_column_index >= self.board.size() {
+            if self.graph.is_terminal(node) && possible {
+                self.record(row_index, column_index, rack, partial_word);
+            }
+            return;
         }
-    } else {
-        let tile = board.primary[row_index][current_column_index];
+        let tile = self.board.primary[(row_index, current_column_index)];
         if tile == 0 {
-            if node.is_terminal && possible {
-                legal_moves.push(LegalMove::new(
-                    board,
-                    value_set,
-                    row_index,
-                    column_index,
-                    board.across.clone(),
-                    partial_word.clone(),
-                    rack.is_empty(),
-                ));
+            if self.graph.is_terminal(node) && possible {
+                self.record(row_index, column_index, rack, partial_word);
             }
-            for (letter, current_node) in node.children.iter() {
-                if (cross_check_sets[row_index][current_column_index] & (1 << *letter - 1)) != 0
-                    && rack.contains_key(letter)
-                {
-                    partial_word.push((*letter as Letter).to_char());
-                    remove_letter_from_rack(rack, *letter);
-                    extend_right(
-                        board,
-                        cross_check_sets,
-                        value_set,
-                        legal_moves,
+            for letter in letters_in(self.playable_letters(node, row_index, current_column_index)) {
+                let current_node = self.graph.child(node, letter).unwrap();
+                for rack_tile in rack_tiles_for(rack, letter).into_iter().flatten() {
+                    partial_word.push(played_tile(rack_tile, letter));
+                    remove_letter_from_rack(rack, rack_tile);
+                    self.extend_right(
                         row_index,
                         column_index,
                         current_node,
@@ -211,121 +337,80 @@ fn extend_right(
                         partial_word,
                         true,
                     );
-                    add_letter_to_rack(rack, *letter);
+                    add_letter_to_rack(rack, rack_tile);
                     partial_word.pop();
                 }
             }
-        } else {
-            if let Some(current_node) = node.children.get(&tile) {
-                partial_word.push((tile as Letter).to_char());
-                extend_right(
-                    board,
-                    cross_check_sets,
-                    value_set,
-                    legal_moves,
-                    row_index,
-                    column_index,
-                    current_node,
-                    rack,
-                    partial_word,
-                    true,
-                );
-                partial_word.pop();
-            }
+        } else if let Some(current_node) = self.graph.child(node, tile.unblanked()) {
+            partial_word.push(tile);
+            self.extend_right(
+                row_index,
+                column_index,
+                current_node,
+                rack,
+                partial_word,
+                true,
+            );
+            partial_word.pop();
         }
     }
-}
 
-fn left_part(
-    board: &Board,
-    cross_check_sets: &[[u32; 15]; 15],
-    value_set: &[[u16; 15]; 15],
-    legal_moves: &mut Vec<LegalMove>,
-    row_index: usize,
-    column_index: usize,
-    node: &Node,
-    rack: &mut HashMap<Letter, u8>,
-    partial_word: &mut String,
-    limit: u8,
-) {
-    extend_right(
-        board,
-        cross_check_sets,
-        value_set,
-        legal_moves,
-        row_index,
-        column_index,
-        node,
-        rack,
-        partial_word,
-        false,
-    );
-    if limit > 0 {
-        for (letter, current_node) in node.children.iter() {
-            if rack.contains_key(letter) {
-                partial_word.push((*letter as Letter).to_char());
-                remove_letter_from_rack(rack, *letter);
-                left_part(
-                    board,
-                    cross_check_sets,
-                    value_set,
-                    legal_moves,
-                    row_index,
-                    column_index - 1,
-                    current_node,
-                    rack,
-                    partial_word,
-                    limit - 1,
-                );
-                add_letter_to_rack(rack, *letter);
-                partial_word.pop();
+    fn left_part(
+        &mut self,
+        row_index: usize,
+        column_index: usize,
+        node: NodeId,
+        rack: &mut HashMap<Letter, u8>,
+        partial_word: &mut Vec<Letter>,
+        limit: u8,
+    ) {
+        self.extend_right(row_index, column_index, node, rack, partial_word, false);
+        if limit > 0 {
+            for letter in letters_in(self.graph.edges(node)) {
+                let current_node = self.graph.child(node, letter).unwrap();
+                for rack_tile in rack_tiles_for(rack, letter).into_iter().flatten() {
+                    partial_word.push(played_tile(rack_tile, letter));
+                    remove_letter_from_rack(rack, rack_tile);
+                    self.left_part(
+                        row_index,
+                        column_index - 1,
+                        current_node,
+                        rack,
+                        partial_word,
+                        limit - 1,
+                    );
+                    add_letter_to_rack(rack, rack_tile);
+                    partial_word.pop();
+                }
             }
         }
     }
-}
 
-pub fn calculate_legal_moves(
-    lexicon: &Lexicon,
-    board: &Board,
-    rack: &mut HashMap<Letter, u8>,
-) -> Vec<LegalMove> {
-    let mut legal_moves = Vec::new();
-    let mut anchors = calculate_anchors(board);
-    if anchors[7] == 0 {
-        anchors[7] = 128;
-    }
-    let (cross_check_sets, value_set) = calculate_cross_check_sets_and_value_set(lexicon, board);
-    for (row_index, row) in board.primary.iter().enumerate() {
-        let mut non_anchor_square_count: u8 = 0;
-        let mut partial_word = String::new();
-        for (column_index, tile) in row.iter().enumerate() {
-            let letter = (*tile as Letter).to_char();
-            match (anchors[row_index] & (1 << column_index)) != 0 {
-                true => {
+    /// Appel-Jacobson generation over a DAWG: every anchor is preceded by a
+    /// left part (either the tiles already there or one built from the rack),
+    /// then extended to the right.
+    fn generate_with_dawg(&mut self, rack: &mut HashMap<Letter, u8>) {
+        for row_index in 0..self.board.size() {
+            let mut non_anchor_square_count: u8 = 0;
+            let mut partial_word = Vec::new();
+            for column_index in 0..self.board.size() {
+                let tile = self.board.primary[(row_index, column_index)];
+                if self.is_anchor(row_index, column_index) {
                     if partial_word.is_empty() {
-                        left_part(
-                            board,
-                            &cross_check_sets,
-                            &value_set,
-                            &mut legal_moves,
+                        self.left_part(
                             row_index,
                             column_index,
-                            &lexicon.root,
+                            self.graph.root(),
                             rack,
                             &mut partial_word,
                             non_anchor_square_count,
                         );
                     } else {
-                        let current_node = lexicon.root.get_node(&partial_word);
-                        if let Some(current_node) = current_node {
-                            extend_right(
-                                board,
-                                &cross_check_sets,
-                                &value_set,
-                                &mut legal_moves,
+                        if let Some(current_node) = self.graph.get_node(&partial_word) {
+                            self.extend_right(
                                 row_index,
                                 column_index - partial_word.len(),
-                                &current_node,
+                                current_node,
                                 rack,
                                 &mut partial_word,
                                 false,
@@ -334,15 +419,293 @@ pub fn calculate_legal_moves(
                         partial_word.clear();
                     }
                     non_anchor_square_count = 0;
-                }
-                false => {
-                    if board.primary[row_index][column_index] != 0 {
-                        partial_word.push(letter);
+                } else {
+                    if tile != 0 {
+                        partial_word.push(tile);
                     }
                     non_anchor_square_count += 1
                 }
             }
         }
     }
-    legal_moves
+
+    /// Places a letter on the square at `column_index`, at or left of the
+    /// anchor, walking the reversed prefix of a GADDAG path.
+    fn gaddag_left(
+        &mut self,
+        row_index: usize,
+        anchor_index: usize,
+        column_index: usize,
+        node: NodeId,
+        rack: &mut HashMap<Letter, u8>,
+        partial_word: &mut Vec<Letter>,
+    ) {
+        let tile = self.board.primary[(row_index, column_index)];
+        if tile != 0 {
+            if let Some(current_node) = self.graph.child(node, tile.unblanked()) {
+                partial_word.insert(0, tile);
+                self.gaddag_after_left(
+                    row_index,
+                    anchor_index,
+                    column_index,
+                    current_node,
+                    rack,
+                    partial_word,
+                );
+                partial_word.remove(0);
+            }
+            return;
+        }
+        for letter in letters_in(self.playable_letters(node, row_index, column_index)) {
+            let current_node = self.graph.child(node, letter).unwrap();
+            for rack_tile in rack_tiles_for(rack, letter).into_iter().flatten() {
+                partial_word.insert(0, played_tile(rack_tile, letter));
+                remove_letter_from_rack(rack, rack_tile);
+                self.gaddag_after_left(
+                    row_index,
+                    anchor_index,
+                    column_index,
+                    current_node,
+                    rack,
+                    partial_word,
+                );
+                add_letter_to_rack(rack, rack_tile);
+                partial_word.remove(0);
+            }
+        }
+    }
+
+    fn gaddag_after_left(
+        &mut self,
+        row_index: usize,
+        anchor_index: usize,
+        column_index: usize,
+        node: NodeId,
+        rack: &mut HashMap<Letter, u8>,
+        partial_word: &mut Vec<Letter>,
+    ) {
+        let last = self.board.size() - 1;
+        let left_is_free =
+            column_index == 0 || self.board.primary[(row_index, column_index - 1)] == 0;
+        if left_is_free {
+            if let Some(separator_node) = self.graph.child(node, SEPARATOR) {
+                let right_is_free =
+                    anchor_index == last || self.board.primary[(row_index, anchor_index + 1)] == 0;
+                if right_is_free && self.graph.is_terminal(separator_node) {
+                    self.record(row_index, column_index, rack, partial_word);
+                }
+                if anchor_index < last {
+                    self.gaddag_right(
+                        row_index,
+                        column_index,
+                        anchor_index + 1,
+                        separator_node,
+                        rack,
+                        partial_word,
+                    );
+                }
+            }
+        }
+        // Squares left of another anchor are covered when that anchor is
+        // processed, so the prefix never reaches over one.
+        if column_index > 0 && (!left_is_free || !self.is_anchor(row_index, column_index - 1)) {
+            self.gaddag_left(
+                row_index,
+                anchor_index,
+                column_index - 1,
+                node,
+                rack,
+                partial_word,
+            );
+        }
+    }
+
+    /// Places a letter on the square at `column_index`, right of the anchor,
+    /// once the GADDAG separator has been crossed.
+    fn gaddag_right(
+        &mut self,
+        row_index: usize,
+        start_index: usize,
+        column_index: usize,
+        node: NodeId,
+        rack: &mut HashMap<Letter, u8>,
+        partial_word: &mut Vec<Letter>,
+    ) {
+        let tile = self.board.primary[(row_index, column_index)];
+        if tile != 0 {
+            if let Some(current_node) = self.graph.child(node, tile.unblanked()) {
+                partial_word.push(tile);
+                self.gaddag_after_right(
+                    row_index,
+                    start_index,
+                    column_index,
+                    current_node,
+                    rack,
+                    partial_word,
+                );
+                partial_word.pop();
+            }
+            return;
+        }
+        for letter in letters_in(self.playable_letters(node, row_index, column_index)) {
+            let current_node = self.graph.child(node, letter).unwrap();
+            for rack_tile in rack_tiles_for(rack, letter).into_iter().flatten() {
+                partial_word.push(played_tile(rack_tile, letter));
+                remove_letter_from_rack(rack, rack_tile);
+                self.gaddag_after_right(
+                    row_index,
+                    start_index,
+                    column_index,
+                    current_node,
+                    rack,
+                    partial_word,
+                );
+                add_letter_to_rack(rack, rack_tile);
+                partial_word.pop();
+            }
+        }
+    }
+
+    fn gaddag_after_right(
+        &mut self,
+        row_index: usize,
+        start_index: usize,
+        column_index: usize,
+        node: NodeId,
+        rack: &mut HashMap<Letter, u8>,
+        partial_word: &mut Vec<Letter>,
+    ) {
+        let last = self.board.size() - 1;
+        let right_is_free =
+            column_index == last || self.board.primary[(row_index, column_index + 1)] == 0;
+        if right_is_free && self.graph.is_terminal(node) {
+            self.record(row_index, start_index, rack, partial_word);
+        }
+        if column_index < last {
+            self.gaddag_right(
+                row_index,
+                start_index,
+                column_index + 1,
+                node,
+                rack,
+                partial_word,
+            );
+        }
+    }
+
+    /// Gordon's GADDAG generation: words are grown leftwards from each
+    /// anchor, then rightwards once the separator is reached.
+    fn generate_with_gaddag(&mut self, rack: &mut HashMap<Letter, u8>) {
+        let mut partial_word = Vec::new();
+        for row_index in 0..self.board.size() {
+            for column_index in 0..self.board.size() {
+                if self.is_anchor(row_index, column_index) {
+                    self.gaddag_left(
+                        row_index,
+                        column_index,
+                        column_index,
+                        self.graph.root(),
+                        rack,
+                        &mut partial_word,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Lists every legal move on `board`, across and down, each once.
+///
+/// A lone tile forming words both ways is found by both passes, and a word
+/// may be reached from several anchors: moves placing the same tiles are
+/// kept only the first time, across moves coming first.
+pub fn calculate_legal_moves(
+    lexicon: &Lexicon,
+    board: &Board,
+    rack: &mut HashMap<Letter, u8>,
+) -> Result<Vec<LegalMove>, Error> {
+    validate_rack(rack, &board.ruleset().alphabet)?;
+    let mut board = board.clone();
+    let mut legal_moves = moves_along_rows(lexicon, &board, rack);
+    board.rotate();
+    legal_moves.append(&mut moves_along_rows(lexicon, &board, rack));
+    let mut seen = HashSet::new();
+    legal_moves.retain(|legal_move| seen.insert(legal_move.placed_tiles.clone()));
+    Ok(legal_moves)
+}
+
+/// Lists every distinct exchange of `rack`, as the tiles thrown back and the
+/// tiles kept, both sorted. Racks holding the same letter twice have fewer
+/// exchanges than sets of tiles, throwing either copy being the same.
+pub fn calculate_exchanges(rack: &[Letter]) -> Vec<(Vec<Letter>, Vec<Letter>)> {
+    let mut rack = rack.to_vec();
+    rack.sort_unstable();
+    let mut exchanges = vec![(Vec::new(), Vec::new())];
+    let mut start = 0;
+    while start < rack.len() {
+        let tile = rack[start];
+        let copies = rack[start..]
+            .iter()
+            .take_while(|other| **other == tile)
+            .count();
+        // Each exchange so far is extended by throwing from none to all of
+        // the copies of the tile, keeping the others.
+        exchanges = exchanges
+            .into_iter()
+            .flat_map(|(thrown, kept): (Vec<Letter>, Vec<Letter>)| {
+                (0..=copies).map(move |number| {
+                    let mut thrown = thrown.clone();
+                    let mut kept = kept.clone();
+                    thrown.extend(std::iter::repeat_n(tile, number));
+                    kept.extend(std::iter::repeat_n(tile, copies - number));
+                    (thrown, kept)
+                })
+            })
+            .collect();
+        start += copies;
+    }
+    exchanges.retain(|(thrown, _)| !thrown.is_empty());
+    exchanges
+}
+
+/// Lists the moves along the rows of `board.primary`, i.e. the across moves
+/// of an unrotated board and the down moves of a rotated one.
+fn moves_along_rows(
+    lexicon: &Lexicon,
+    board: &Board,
+    rack: &mut HashMap<Letter, u8>,
+) -> Vec<LegalMove> {
+    let mut anchors = calculate_anchors(board);
+    let centre = board.centre();
+    if anchors.cells().all(|anchor| !anchor) && board.primary[centre] == 0 {
+        anchors[centre] = true;
+    }
+    let cross_check_sets = calculate_cross_check_sets(lexicon, board);
+    let rack_tile_count = rack.values().map(|count| *count as usize).sum();
+    match &lexicon.gaddag {
+        Some(gaddag) => {
+            let mut generator = MoveGenerator {
+                graph: gaddag,
+                board,
+                anchors,
+                cross_check_sets,
+                rack_tile_count,
+                legal_moves: Vec::new(),
+            };
+            generator.generate_with_gaddag(rack);
+            generator.legal_moves
+        }
+        None => {
+            let mut generator = MoveGenerator {
+                graph: &lexicon.dawg,
+                board,
+                anchors,
+                cross_check_sets,
+                rack_tile_count,
+                legal_moves: Vec::new(),
+            };
+            generator.generate_with_dawg(rack);
+            generator.legal_moves
+        }
+    }
 }
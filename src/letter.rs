@@ -1,35 +1,47 @@
+//! Letters and sets of letters. See [`Alphabet`](crate::alphabet::Alphabet)
+//! for how they are written.
+
+/// A tile: 0 for an empty square, 1 and up for the letters of the
+/// alphabet in order, possibly combined with [`BLANK`].
 pub type Letter = u8;
 
-pub trait ToChar {
-    fn to_char(&self) -> char;
+/// Flag marking a blank tile. On its own it is a blank still sitting in the
+/// rack; combined with a letter it is a blank that has been assigned that letter.
+pub const BLANK: Letter = 0x80;
+
+/// Inspection of the blank flag of a letter.
+pub trait Blank {
+    fn is_blank(&self) -> bool;
+    /// The letter without its blank flag.
+    fn unblanked(&self) -> Letter;
 }
 
-impl ToChar for Letter {
-    fn to_char(&self) -> char {
-        match *self {
-            0 => Ok(' '),
-            1..=26 => Ok((self + 0x40) as char),
-            _ => Err(format!(
-                "Invalid letter value: {self}. Expected a value between 0 and 26. \
-                Only 26 letters (A-Z) and the space character are supported."
-            )),
-        }
-        .unwrap()
+impl Blank for Letter {
+    fn is_blank(&self) -> bool {
+        self & BLANK != 0
     }
-}
 
-pub trait FromChar {
-    fn from_char(letter: char) -> Letter;
+    fn unblanked(&self) -> Letter {
+        self & !BLANK
+    }
 }
 
-impl FromChar for Letter {
-    fn from_char(letter: char) -> Letter {
-        match letter {
-            'A'..='Z' => Ok((letter as u8) - 0x40),
-            ' ' => Ok(0),
-            _ => Err(format!(
-                "Invalid character: '{letter}'. Expected an uppercase letter (A-Z) or a space (' ')."
-            )),
-        }.unwrap()
-    }
+/// A set of letters stored as a bitmask, bit `n` standing for letter `n`.
+pub type LetterSet = u64;
+
+/// Every letter of any alphabet.
+pub const ALL_LETTERS: LetterSet = !1;
+
+/// Iterates over the letters of a set in ascending order.
+pub fn letters_in(set: LetterSet) -> impl Iterator<Item = Letter> {
+    let mut remaining = set;
+    std::iter::from_fn(move || {
+        if remaining == 0 {
+            None
+        } else {
+            let letter = remaining.trailing_zeros() as Letter;
+            remaining &= remaining - 1;
+            Some(letter)
+        }
+    })
 }
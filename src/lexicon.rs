@@ -1,77 +1,292 @@
-use crate::letter::{FromChar, Letter};
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-
-pub struct Node {
-    pub is_terminal: bool,
-    pub children: HashMap<Letter, Node>,
-}
+//! Word lists and the graphs used to walk them.
 
-impl Node {
-    fn new(is_terminal: bool) -> Node {
-        Node {
-            is_terminal,
-            children: HashMap::new(),
-        }
-    }
+use crate::alphabet::Alphabet;
+use crate::dawg::{read_u32, Dawg, DawgBuilder};
+use crate::error::Error;
+use crate::letter::{Blank, Letter, LetterSet, BLANK};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read};
 
-    pub fn get_node(&self, partial_word: &str) -> Option<&Node> {
-        let mut current_node = self;
-        for letter in partial_word.chars() {
-            if current_node
-                .children
-                .contains_key(&Letter::from_char(letter))
-            {
-                current_node = current_node
-                    .children
-                    .get(&Letter::from_char(letter))
-                    .unwrap();
-            } else {
-                return None;
-            }
+/// A node of a [`WordGraph`].
+pub type NodeId = u32;
+
+/// Letter marking, in a GADDAG path, the switch from the reversed prefix to
+/// the suffix. Letter 0 is never part of a word, so its edge bit is free.
+pub const SEPARATOR: Letter = 0;
+
+/// A graph of words that can be walked one letter at a time from its root.
+pub trait WordGraph {
+    fn root(&self) -> NodeId;
+
+    /// Whether the path leading to `node` spells a whole word.
+    fn is_terminal(&self, node: NodeId) -> bool;
+
+    /// The letters labelling the outgoing edges of `node`.
+    fn edges(&self, node: NodeId) -> LetterSet;
+
+    /// The node reached by following the `letter` edge of `node`.
+    fn child(&self, node: NodeId, letter: Letter) -> Option<NodeId>;
+
+    /// Follows `partial_word` from `node`. Blanks are matched by the letter
+    /// they stand for.
+    fn walk(&self, node: NodeId, partial_word: &[Letter]) -> Option<NodeId> {
+        let mut current_node = node;
+        for letter in partial_word {
+            current_node = self.child(current_node, letter.unblanked())?;
         }
         Some(current_node)
     }
+
+    /// Follows `partial_word` from the root.
+    fn get_node(&self, partial_word: &[Letter]) -> Option<NodeId> {
+        self.walk(self.root(), partial_word)
+    }
+}
+
+/// First bytes of a compiled lexicon file.
+const MAGIC: &[u8; 8] = b"SCRABLEX";
+
+/// Bumped whenever the layout of compiled lexicon files changes.
+const FORMAT_VERSION: u16 = 2;
+
+/// Header flag set when the file also holds a GADDAG.
+const HAS_GADDAG: u16 = 1;
+
+/// Whether the file at `path` starts like a compiled lexicon.
+fn is_compiled(path: &str) -> bool {
+    let mut magic = [0; MAGIC.len()];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok()
+        && &magic == MAGIC
 }
 
+/// 32-bit FNV-1a hash, used to detect corrupted compiled lexicons.
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c9dc5, |hash, byte| {
+        (hash ^ *byte as u32).wrapping_mul(0x01000193)
+    })
+}
+
+/// The words that may be played.
 pub struct Lexicon {
-    pub root: Node,
+    /// The alphabet the words are written in, which must be the one of the
+    /// ruleset of the boards they are played on.
+    pub alphabet: Alphabet,
+    pub dawg: Dawg,
+    /// Built on demand with [`Lexicon::with_gaddag`]; when present the move
+    /// generator grows words outwards from each anchor instead of running
+    /// a separate left-part pass.
+    pub gaddag: Option<Dawg>,
 }
 
 impl Lexicon {
-    pub fn new() -> Lexicon {
+    /// Builds a lexicon from words given as letters of `alphabet`, in any
+    /// order.
+    pub fn from_words(alphabet: Alphabet, mut words: Vec<Vec<Letter>>) -> Lexicon {
+        words.sort_unstable();
+        words.dedup();
         Lexicon {
-            root: Node::new(false),
+            alphabet,
+            dawg: Dawg::from_sorted(words.iter().map(Vec::as_slice)),
+            gaddag: None,
+        }
+    }
+
+    /// Loads a lexicon from either a plain word list or a compiled file,
+    /// telling them apart by the compiled file header. Word lists are read
+    /// in the Latin alphabet and compiled files in the one they were
+    /// compiled for.
+    pub fn open(path: &str) -> Result<Self, Error> {
+        if is_compiled(path) {
+            Lexicon::from_compiled_file(path)
+        } else {
+            Lexicon::from_file(path, &Alphabet::latin())
+        }
+    }
+
+    /// Like [`Lexicon::open`], but word lists are read in `alphabet` and
+    /// compiled files must have been compiled for it.
+    pub fn open_with_alphabet(path: &str, alphabet: &Alphabet) -> Result<Self, Error> {
+        if !is_compiled(path) {
+            return Lexicon::from_file(path, alphabet);
+        }
+        let lexicon = Lexicon::from_compiled_file(path)?;
+        if lexicon.alphabet != *alphabet {
+            return Err(Error::InvalidLexicon(format!(
+                "The lexicon was compiled for the alphabet '{}' instead of '{}'.",
+                lexicon.alphabet.tokens().collect::<Vec<_>>().join(" "),
+                alphabet.tokens().collect::<Vec<_>>().join(" ")
+            )));
         }
+        Ok(lexicon)
     }
 
-    pub fn from_file(path: &str) -> Self {
-        let file = File::open(path).unwrap_or_else(|_| {
-            panic!(
-                "Failed to open the lexicon file at '{}'. Ensure the file exists and is readable.",
-                path
-            );
-        });
+    /// Reads a plain word list in `alphabet`, one word per line.
+    pub fn from_file(path: &str, alphabet: &Alphabet) -> Result<Self, Error> {
+        let io_error = |source| Error::Io {
+            path: path.to_string(),
+            source,
+        };
+        let file = File::open(path).map_err(io_error)?;
 
         let reader = BufReader::new(file);
-        let mut lexicon = Lexicon::new();
-
-        for line_result in reader.lines() {
-            let word = line_result.unwrap_or_else(|_| {
-                panic!("Failed to read a line from the lexicon file. Ensure it contains valid UTF-8 data.");
-            });
-
-            let mut current_node = &mut lexicon.root;
-            for letter in word.chars() {
-                current_node = current_node
-                    .children
-                    .entry(Letter::from_char(letter))
-                    .or_insert(Node::new(false));
+        let mut words = Vec::new();
+
+        for (index, line_result) in reader.lines().enumerate() {
+            let word = line_result.map_err(io_error)?;
+            if word.is_empty() {
+                continue;
             }
-            current_node.is_terminal = true;
+            let letters = alphabet
+                .parse(&word)
+                .ok()
+                .filter(|letters| !letters.contains(&BLANK))
+                .ok_or_else(|| Error::InvalidWord {
+                    line: index + 1,
+                    word: word.clone(),
+                })?;
+            words.push(letters.iter().map(Letter::unblanked).collect());
         }
 
-        lexicon
+        Ok(Lexicon::from_words(alphabet.clone(), words))
+    }
+
+    /// Whether `word` is in the lexicon. Blanks are matched by the letter
+    /// they stand for.
+    pub fn contains(&self, word: &str) -> bool {
+        self.alphabet
+            .parse(word)
+            .is_ok_and(|letters| self.contains_letters(letters.as_slice()))
+    }
+
+    /// Like [`Lexicon::contains`] for a word given as letters.
+    pub fn contains_letters(&self, word: &[Letter]) -> bool {
+        self.dawg
+            .get_node(word)
+            .is_some_and(|node| self.dawg.is_terminal(node))
+    }
+
+    /// Adds a GADDAG built from the words of the lexicon. For every word
+    /// `w` and split point `i`, it accepts `rev(w[..i]) SEPARATOR w[i..]`.
+    pub fn with_gaddag(mut self) -> Self {
+        let words = self.dawg.sequences();
+        let mut builder = DawgBuilder::new();
+        let mut sequences = Vec::new();
+        // Paths are generated one starting letter at a time so only a slice
+        // of them is held in memory while they are sorted.
+        for first_letter in self.alphabet.letters() {
+            sequences.clear();
+            for word in &words {
+                for split in 1..=word.len() {
+                    if word[split - 1] != first_letter {
+                        continue;
+                    }
+                    let mut sequence: Vec<Letter> = word[..split].iter().rev().copied().collect();
+                    sequence.push(SEPARATOR);
+                    sequence.extend_from_slice(&word[split..]);
+                    sequences.push(sequence);
+                }
+            }
+            sequences.sort_unstable();
+            for sequence in &sequences {
+                builder.insert(sequence);
+            }
+        }
+        self.gaddag = Some(builder.finish());
+        self
+    }
+
+    /// Writes the lexicon in the compiled format read back by
+    /// [`Lexicon::from_compiled_file`].
+    ///
+    /// The file starts with the magic bytes, the format version, flags, the
+    /// alphabet as its letter count followed by each token prefixed with its
+    /// length, the word count and a checksum of the rest of the file, which
+    /// holds the DAWG followed by the GADDAG when there is one.
+    pub fn save(&self, path: &str) -> Result<(), Error> {
+        let mut payload = Vec::new();
+        self.dawg.write_to(&mut payload);
+        if let Some(gaddag) = &self.gaddag {
+            gaddag.write_to(&mut payload);
+        }
+
+        let mut bytes = Vec::with_capacity(payload.len() + 64);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        let flags = if self.gaddag.is_some() { HAS_GADDAG } else { 0 };
+        bytes.extend_from_slice(&flags.to_le_bytes());
+        bytes.push(self.alphabet.letter_count() as u8);
+        for token in self.alphabet.tokens() {
+            bytes.push(token.len() as u8);
+            bytes.extend_from_slice(token.as_bytes());
+        }
+        bytes.extend_from_slice(&(self.dawg.sequence_count() as u32).to_le_bytes());
+        bytes.extend_from_slice(&checksum(&payload).to_le_bytes());
+        bytes.extend_from_slice(&payload);
+        fs::write(path, bytes).map_err(|source| Error::Io {
+            path: path.to_string(),
+            source,
+        })
+    }
+
+    /// Reads a file written by [`Lexicon::save`].
+    pub fn from_compiled_file(path: &str) -> Result<Self, Error> {
+        let bytes = fs::read(path).map_err(|source| Error::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        Lexicon::from_compiled_bytes(&bytes)
+    }
+
+    fn from_compiled_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let invalid = |message: String| Err(Error::InvalidLexicon(message));
+        let truncated = || Error::InvalidLexicon("The file is truncated.".to_string());
+        if !bytes.starts_with(MAGIC) {
+            return invalid("This is not a compiled lexicon.".to_string());
+        }
+        let mut position = MAGIC.len();
+        let header = bytes.get(position..position + 5).ok_or_else(truncated)?;
+        let version = u16::from_le_bytes([header[0], header[1]]);
+        if version != FORMAT_VERSION {
+            return invalid(format!(
+                "Unsupported format version {version}, expected {FORMAT_VERSION}. \
+                Compile the word list again."
+            ));
+        }
+        let flags = u16::from_le_bytes([header[2], header[3]]);
+        let letter_count = header[4] as usize;
+        position += 5;
+        let mut tokens = Vec::with_capacity(letter_count);
+        for _ in 0..letter_count {
+            let length = *bytes.get(position).ok_or_else(truncated)? as usize;
+            let token = bytes
+                .get(position + 1..position + 1 + length)
+                .ok_or_else(truncated)?;
+            tokens.push(String::from_utf8_lossy(token).into_owned());
+            position += 1 + length;
+        }
+        let alphabet = Alphabet::new(&tokens)
+            .map_err(|message| Error::InvalidLexicon(format!("Invalid alphabet: {message}.")))?;
+        let word_count = read_u32(bytes, &mut position)? as usize;
+        let expected_checksum = read_u32(bytes, &mut position)?;
+        if checksum(&bytes[position..]) != expected_checksum {
+            return invalid("The checksum does not match, the file is corrupted.".to_string());
+        }
+
+        let dawg = Dawg::read_from(bytes, &mut position)?;
+        let gaddag = if flags & HAS_GADDAG != 0 {
+            Some(Dawg::read_from(bytes, &mut position)?)
+        } else {
+            None
+        };
+        if dawg.sequence_count() != word_count {
+            return invalid("The word count does not match the word graph.".to_string());
+        }
+        Ok(Lexicon {
+            alphabet,
+            dawg,
+            gaddag,
+        })
     }
 }
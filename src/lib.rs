@@ -0,0 +1,55 @@
+//! A Scrabble solver finding the highest-scoring moves of a position, based
+//! on "The World's Fastest Scrabble Program" by Andrew W. Appel and Guy J.
+//! Jacobson.
+//!
+//! [`Solver`] is the entry point: it owns a [`Lexicon`] and lists the legal
+//! moves of a [`Board`] for a given rack.
+//!
+//! ```no_run
+//! use scrabble::{Board, Lexicon, Solver};
+//!
+//! let lexicon = Lexicon::open("src/dictionaries/ods8.txt")?;
+//! let solver = Solver::new(lexicon);
+//! let mut board = Board::new();
+//! let moves = solver.moves(&board, "SCRABB?")?;
+//! if let Some(best) = moves.first() {
+//!     solver.play(&mut board, &best.letters, best.row_index, best.column_index, best.across)?;
+//! }
+//! # Ok::<(), scrabble::Error>(())
+//! ```
+
+pub mod alphabet;
+pub mod bag;
+pub mod board;
+pub mod dawg;
+pub mod endgame;
+pub mod error;
+pub mod game;
+pub mod gcg;
+pub mod grid;
+pub mod leave;
+pub mod legal_moves;
+pub mod letter;
+pub mod lexicon;
+pub mod notation;
+pub mod position;
+pub mod preendgame;
+pub mod random;
+pub mod ruleset;
+pub mod score;
+pub mod simulation;
+mod solver;
+pub mod tournament;
+
+pub use alphabet::Alphabet;
+pub use bag::UnseenTiles;
+pub use board::{Board, Placement};
+pub use error::{Error, PlacementError, TurnError};
+pub use game::Game;
+pub use leave::Leaves;
+pub use legal_moves::{LegalMove, Move};
+pub use lexicon::Lexicon;
+pub use position::Position;
+pub use ruleset::Ruleset;
+pub use simulation::Simulation;
+pub use solver::Solver;
@@ -1,104 +1,186 @@
-mod board;
-mod legal_moves;
-mod letter;
-mod lexicon;
-mod score;
+mod commands;
 
-use board::Board;
-use legal_moves::{add_letter_to_rack, calculate_legal_moves};
-use letter::{FromChar, Letter};
-use lexicon::Lexicon;
-use std::collections::HashMap;
+use commands::{describe_breakdown, Arguments, Failure};
+use scrabble::notation::MoveNotation;
+use scrabble::{Board, Position, Solver};
 use std::io::{stdin, stdout, Write};
+use std::process::ExitCode;
 
 fn get_user_input() -> String {
     let mut result = String::new();
     let _ = stdout().flush();
-    stdin()
+    let length = stdin()
         .read_line(&mut result)
         .expect("Did not enter a correct string");
+    if length == 0 {
+        // Standard input was closed, so no further choice can be made.
+        std::process::exit(0);
+    }
     if let Some('\n') = result.chars().next_back() {
         result.pop();
     }
     if let Some('\r') = result.chars().next_back() {
         result.pop();
     }
-    return result;
+    result
 }
 
 fn clear_screen() {
     print!("{}[2J", 27 as char);
 }
 
-fn main() -> Result<(), String> {
-    let mut board: Board = Board::new();
-    let lexicon = Lexicon::from_file("src/dictionaries/ods8.txt");
-    let mut rack = HashMap::new();
+/// Reads a number between 1 and `maximum` and returns it as an index.
+fn get_index(prompt: &str, maximum: usize) -> Result<usize, String> {
+    print!("{prompt}");
+    match get_user_input().parse::<usize>() {
+        Ok(number) if (1..=maximum).contains(&number) => Ok(number - 1),
+        _ => Err(format!("Please enter a number between 1 and {maximum}")),
+    }
+}
+
+fn pause() {
+    print!("Press Enter to continue");
+    get_user_input();
+}
+
+fn place_word(solver: &Solver, board: &mut Board) -> Result<(), String> {
+    print!("Move (8H WORD goes across, H8 WORD down, lowercase for a blank): ");
+    let alphabet = board.ruleset().alphabet.clone();
+    let notation =
+        MoveNotation::parse(&get_user_input(), &alphabet).map_err(|error| error.to_string())?;
+    if !notation.agrees_with(board) {
+        return Err("The letters between parentheses are not the tiles on the board".to_string());
+    }
+    let placement = solver
+        .play(
+            board,
+            &notation.word(),
+            notation.row_index,
+            notation.column_index,
+            notation.across,
+        )
+        .map_err(|error| error.to_string())?;
+    let rack_tiles = alphabet.write(&placement.rack_tiles);
+    println!(
+        "{} scores {} points using the tiles {}",
+        placement.words.join(", "),
+        placement.score,
+        rack_tiles
+    );
+    println!("{}", describe_breakdown(&placement.breakdown, &alphabet));
+    pause();
+    Ok(())
+}
+
+fn calculate_best_moves(solver: &Solver, board: &mut Board) -> Result<(), String> {
+    print!("Rack (? for a blank): ");
+    let mut legal_moves = solver
+        .moves(board, &get_user_input())
+        .map_err(|error| error.to_string())?;
+    println!(
+        "In total, {} words were found by the algorithm.",
+        legal_moves.len()
+    );
+    if legal_moves.is_empty() {
+        return Ok(());
+    }
+    println!("Here is a list of the 30 highest-scoring words: ");
+    legal_moves.truncate(30);
+    for (index, legal_move) in legal_moves.iter().enumerate() {
+        println!(
+            "[{}] {} ({} pts)",
+            index + 1,
+            MoveNotation::from_move(legal_move, board),
+            legal_move.score
+        );
+    }
+    let word_index = get_index(
+        "Number corresponding to the word to play: ",
+        legal_moves.len(),
+    )?;
+    let word = &legal_moves[word_index];
+    board
+        .play(
+            &word.letters,
+            word.row_index,
+            word.column_index,
+            word.across,
+        )
+        .map_err(|error| error.to_string())
+}
+
+fn save_board(board: &Board) -> Result<(), String> {
+    print!("File to save the board to: ");
+    Position::new(board.clone())
+        .save(&get_user_input())
+        .map_err(|error| error.to_string())
+}
+
+fn load_board(board: &mut Board) -> Result<(), String> {
+    print!("File to load the board from: ");
+    *board = Position::load(&get_user_input(), board.shared_ruleset())
+        .map_err(|error| error.to_string())?
+        .board;
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let result = match arguments.first().map(String::as_str) {
+        Some("solve") => commands::solve(&arguments[1..]),
+        Some("simulate") => commands::simulate(&arguments[1..]),
+        Some("endgame") => commands::endgame(&arguments[1..]),
+        Some("preendgame") => commands::preendgame(&arguments[1..]),
+        Some("tournament") => commands::tournament(&arguments[1..]),
+        Some("play") => commands::play(&arguments[1..]),
+        Some("replay") => commands::replay(&arguments[1..]),
+        Some("unseen") => commands::unseen(&arguments[1..]),
+        Some("check") => commands::check(&arguments[1..]),
+        Some("compile") => commands::compile(&arguments[1..]),
+        Some("help" | "--help" | "-h") => {
+            println!("{}", commands::USAGE);
+            Ok(())
+        }
+        _ => interactive(&arguments),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => failure.report(),
+    }
+}
+
+/// Runs the interactive menu until the user quits.
+fn interactive(arguments: &[String]) -> Result<(), Failure> {
+    let arguments = Arguments::parse(arguments, &["lexicon", "rules"])?;
+    // The lexicon path used to be given as the only positional argument.
+    let lexicon_path = match arguments.positional.as_slice() {
+        [] => arguments.lexicon_path(),
+        [path] if arguments.option("lexicon").is_none() => path,
+        _ => return Err(Failure::Usage("Unexpected arguments".to_string())),
+    };
+    let solver = Solver::new(arguments.lexicon(lexicon_path)?);
+    let mut board = Board::with_ruleset(arguments.ruleset()?);
     loop {
         clear_screen();
-        rack.clear();
         println!("{}", board);
         println!("What do you want to do?");
         println!("[1] Place a word on the board");
         println!("[2] Calculate the best moves");
-        println!("[3] Quit");
+        println!("[3] Save the board");
+        println!("[4] Load a board");
+        println!("[5] Quit");
         print!(": ");
-        match get_user_input().parse::<u8>() {
-            Ok(1) => {
-                print!("Word: ");
-                let word = get_user_input();
-                print!("Row number: ");
-                let row = get_user_input().parse::<usize>().unwrap();
-                print!("Column number: ");
-                let column = get_user_input().parse::<usize>().unwrap();
-                println!("Choose the orientation of the word");
-                println!("[1] Horizontal");
-                println!("[2] Vertical");
-                print!(": ");
-                let across;
-                match get_user_input().parse::<u8>() {
-                    Ok(1) => {
-                        across = true;
-                    }
-                    Ok(2) => {
-                        across = false;
-                    }
-                    _ => {
-                        println!("Please enter a valid number");
-                        continue;
-                    }
-                }
-                board.play(&word, row - 1, column - 1, across);
-            }
-            Ok(2) => {
-                print!("Rack: ");
-                for letter in get_user_input().chars() {
-                    add_letter_to_rack(&mut rack, Letter::from_char(letter));
-                }
-                let mut legal_moves = calculate_legal_moves(&lexicon, &board, &mut rack);
-                board.rotate();
-                legal_moves.append(&mut calculate_legal_moves(&lexicon, &board, &mut rack));
-                board.rotate();
-                legal_moves.sort_by(|a, b| b.score.cmp(&a.score));
-                println!(
-                    "In total, {} words were found by the algorithm.",
-                    legal_moves.len()
-                );
-                println!("Here is a list of the 30 highest-scoring words: ");
-                legal_moves.truncate(30);
-                for (index, legal_move) in legal_moves.iter().enumerate() {
-                    println!("[{}] {} horizontal: {} row: {} column: {} ({} pts)", index + 1, legal_move.letters, legal_move.across, legal_move.row_index + 1, legal_move.column_index + 1, legal_move.score);
-                }
-                print!("Number corresponding to the word to play: ");
-                let word_index = get_user_input().parse::<usize>().unwrap() - 1;
-                let word = &legal_moves[word_index];
-                board.play(&word.letters, word.row_index, word.column_index, word.across);
-            }
-            Ok(3) => break Ok(()),
-            _ => println!("Please enter a valid number"),
-        }
-        for letter in "".chars() {
-            add_letter_to_rack(&mut rack, Letter::from_char(letter));
+        let result = match get_user_input().parse::<u8>() {
+            Ok(1) => place_word(&solver, &mut board),
+            Ok(2) => calculate_best_moves(&solver, &mut board),
+            Ok(3) => save_board(&board),
+            Ok(4) => load_board(&mut board),
+            Ok(5) => break Ok(()),
+            _ => Err("Please enter a valid number".to_string()),
+        };
+        if let Err(message) = result {
+            println!("{message}");
+            pause();
         }
     }
 }
@@ -0,0 +1,188 @@
+//! The standard notation of moves, as used by players and game records.
+//!
+//! A move is written as the coordinate of its first letter followed by the
+//! word. A coordinate starting with the row number, such as `8H`, is an
+//! across move, and one starting with the column letter, such as `H8`, a
+//! down move. Tiles already on the board are put between parentheses and
+//! blanks are written in lowercase: `8H WO(R)d`.
+
+use crate::alphabet::Alphabet;
+use crate::board::Board;
+use crate::error::Error;
+use crate::legal_moves::LegalMove;
+use crate::letter::BLANK;
+use crate::ruleset::MAX_BOARD_SIZE;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Reads a coordinate, returning the square it names and whether the move
+/// goes across. The square may lie outside a board smaller than
+/// [`MAX_BOARD_SIZE`], which placing the move then reports.
+pub fn parse_coordinate(text: &str) -> Option<(usize, usize, bool)> {
+    let first = text.chars().next().filter(char::is_ascii)?;
+    let (column, row, across) = if first.is_ascii_digit() {
+        let split = text.find(|character: char| !character.is_ascii_digit())?;
+        (&text[split..], &text[..split], true)
+    } else {
+        (&text[..1], &text[1..], false)
+    };
+    let [column] = column.as_bytes() else {
+        return None;
+    };
+    let column_index = column.to_ascii_uppercase().checked_sub(b'A')? as usize;
+    let row_index = row.parse::<usize>().ok()?.checked_sub(1)?;
+    (row_index < MAX_BOARD_SIZE && column_index < MAX_BOARD_SIZE).then_some((
+        row_index,
+        column_index,
+        across,
+    ))
+}
+
+/// Writes the coordinate of a square for a move in the given direction.
+pub fn format_coordinate(row_index: usize, column_index: usize, across: bool) -> String {
+    let column = (b'A' + column_index as u8) as char;
+    if across {
+        format!("{}{column}", row_index + 1)
+    } else {
+        format!("{column}{}", row_index + 1)
+    }
+}
+
+/// A placement written in standard notation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MoveNotation {
+    pub row_index: usize,
+    pub column_index: usize,
+    pub across: bool,
+    /// Each tile of the word as written, blanks in lowercase.
+    pub tiles: Vec<String>,
+    /// For each tile, whether it is already on the board.
+    pub on_board: Vec<bool>,
+}
+
+impl MoveNotation {
+    /// Reads a move whose tiles are letters of `alphabet`. Use
+    /// [`str::parse`] for the Latin alphabet.
+    pub fn parse(text: &str, alphabet: &Alphabet) -> Result<MoveNotation, Error> {
+        let invalid = || Error::InvalidMove(text.to_string());
+        let [coordinate, written_word] = text.split_whitespace().collect::<Vec<_>>()[..] else {
+            return Err(invalid());
+        };
+        let (row_index, column_index, across) = parse_coordinate(coordinate).ok_or_else(invalid)?;
+        let mut tiles = Vec::new();
+        let mut on_board = Vec::new();
+        let mut in_parentheses = false;
+        let mut group_length = 0;
+        let mut rest = written_word;
+        while let Some(character) = rest.chars().next() {
+            let length = match character {
+                '(' if !in_parentheses => {
+                    in_parentheses = true;
+                    group_length = 0;
+                    1
+                }
+                ')' if in_parentheses && group_length > 0 => {
+                    in_parentheses = false;
+                    1
+                }
+                _ => match alphabet.read_tile(rest) {
+                    Some((letter, length)) if letter != BLANK => {
+                        tiles.push(rest[..length].to_string());
+                        on_board.push(in_parentheses);
+                        group_length += 1;
+                        length
+                    }
+                    _ => return Err(invalid()),
+                },
+            };
+            rest = &rest[length..];
+        }
+        // Parentheses must not split a tile, as in `(C)H` for a `CH` tile.
+        if in_parentheses
+            || tiles.is_empty()
+            || alphabet
+                .parse(&tiles.concat())
+                .ok()
+                .map(|letters| letters.len())
+                != Some(tiles.len())
+        {
+            return Err(invalid());
+        }
+        Ok(MoveNotation {
+            row_index,
+            column_index,
+            across,
+            tiles,
+            on_board,
+        })
+    }
+
+    /// The whole word, blanks in lowercase.
+    pub fn word(&self) -> String {
+        self.tiles.concat()
+    }
+
+    /// Whether the letters between parentheses are the tiles the word goes
+    /// through on `board`. A word written without parentheses always agrees.
+    pub fn agrees_with(&self, board: &Board) -> bool {
+        !self.on_board.contains(&true) || self.on_board == self.squares_on_board(board)
+    }
+
+    fn squares_on_board(&self, board: &Board) -> Vec<bool> {
+        (0..self.tiles.len())
+            .map(|index| {
+                let (row, column) = if self.across {
+                    (self.row_index, self.column_index + index)
+                } else {
+                    (self.row_index + index, self.column_index)
+                };
+                row < board.size() && column < board.size() && board.tile(row, column) != 0
+            })
+            .collect()
+    }
+
+    /// Writes a legal move, putting the tiles it goes through between
+    /// parentheses. `board` is the board before the move is played.
+    pub fn from_move(legal_move: &LegalMove, board: &Board) -> MoveNotation {
+        let alphabet = &board.ruleset().alphabet;
+        let mut notation = MoveNotation {
+            row_index: legal_move.row_index,
+            column_index: legal_move.column_index,
+            across: legal_move.across,
+            tiles: alphabet.write_tiles(&alphabet.parse(&legal_move.letters).unwrap_or_default()),
+            on_board: Vec::new(),
+        };
+        notation.on_board = notation.squares_on_board(board);
+        notation
+    }
+}
+
+impl FromStr for MoveNotation {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        MoveNotation::parse(text, &Alphabet::latin())
+    }
+}
+
+impl Display for MoveNotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ",
+            format_coordinate(self.row_index, self.column_index, self.across)
+        )?;
+        let mut in_parentheses = false;
+        for (tile, on_board) in self.tiles.iter().zip(&self.on_board) {
+            if *on_board != in_parentheses {
+                write!(f, "{}", if *on_board { '(' } else { ')' })?;
+                in_parentheses = *on_board;
+            }
+            write!(f, "{tile}")?;
+        }
+        if in_parentheses {
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
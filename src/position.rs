@@ -0,0 +1,205 @@
+//! Plain-text position files, used to save, share and reload a game.
+//!
+//! A position file holds the board as one line per row, 15 lines of 15
+//! squares on the standard board, `.` standing for an empty square and a
+//! lowercase letter for a blank. Squares are read as the letters of the
+//! ruleset's alphabet, so a digraph tile such as `CH` takes two characters.
+//! The board is optionally followed by lines giving the rack, the tiles
+//! left in the bag and the scores of the players:
+//!
+//! ```text
+//! ...............
+//! ...............
+//! ...............
+//! ...............
+//! ...............
+//! ...............
+//! ...............
+//! .......CAT.....
+//! ...............
+//! ...............
+//! ...............
+//! ...............
+//! ...............
+//! ...............
+//! ...............
+//! rack: AEIRST?
+//! bag: ABCDEFGHIJKLMNOP
+//! scores: 120 98
+//! ```
+//!
+//! Empty lines and lines starting with `#` are ignored.
+
+use crate::alphabet::Alphabet;
+use crate::board::Board;
+use crate::error::Error;
+use crate::legal_moves::parse_rack;
+use crate::letter::{Letter, BLANK};
+use crate::ruleset::Ruleset;
+use std::fmt::Display;
+use std::fs;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// A board with what is known of the game around it.
+#[derive(Clone, Debug, Default)]
+pub struct Position {
+    pub board: Board,
+    /// The tiles of the player to move, `?` for a blank.
+    pub rack: Option<String>,
+    /// The tiles left in the bag, `?` for a blank.
+    pub bag: Option<String>,
+    /// The score of each player, in turn order, negative when penalties
+    /// outweigh the points scored.
+    pub scores: Vec<i32>,
+}
+
+impl Position {
+    /// A position with nothing known besides the board.
+    pub fn new(board: Board) -> Position {
+        Position {
+            board,
+            ..Position::default()
+        }
+    }
+
+    /// Reads a position file, the board being played under `ruleset`.
+    pub fn load(path: &str, ruleset: Arc<Ruleset>) -> Result<Position, Error> {
+        let text = fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        Position::parse(&text, ruleset)
+    }
+
+    /// Writes the position to a file, in the format read by [`Position::load`].
+    pub fn save(&self, path: &str) -> Result<(), Error> {
+        fs::write(path, self.to_string()).map_err(|source| Error::Io {
+            path: path.to_string(),
+            source,
+        })
+    }
+}
+
+fn invalid(line: usize, message: String) -> Error {
+    Error::InvalidBoard { line, message }
+}
+
+/// Checks that a rack or bag line only holds tiles.
+fn read_tiles(line: usize, tiles: &str, alphabet: &Alphabet) -> Result<String, Error> {
+    parse_rack(tiles, alphabet).map_err(|error| invalid(line, error.to_string()))?;
+    Ok(tiles.to_string())
+}
+
+impl Position {
+    /// Reads a position, the board being played under `ruleset`. Use
+    /// [`str::parse`] for the default ruleset.
+    pub fn parse(text: &str, ruleset: Arc<Ruleset>) -> Result<Position, Error> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim_end()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+        let mut board = Board::with_ruleset(ruleset);
+        let size = board.size();
+        for row_index in 0..size {
+            let (line_number, line) = lines.next().ok_or_else(|| {
+                invalid(
+                    text.lines().count(),
+                    format!("expected {size} rows, found {row_index}"),
+                )
+            })?;
+            let mut squares = Vec::new();
+            let mut rest = line;
+            while let Some(square) = rest.chars().next() {
+                let (letter, length) = match square {
+                    '.' => (0, 1),
+                    _ => board
+                        .ruleset()
+                        .alphabet
+                        .read_tile(rest)
+                        .filter(|(letter, _)| *letter != BLANK)
+                        .ok_or_else(|| invalid(line_number, format!("'{square}' is not a tile")))?,
+                };
+                squares.push(letter);
+                rest = &rest[length..];
+            }
+            if squares.len() != size {
+                return Err(invalid(
+                    line_number,
+                    format!("expected {size} squares, found {}", squares.len()),
+                ));
+            }
+            for (column_index, letter) in squares.into_iter().enumerate() {
+                board.set(row_index, column_index, letter);
+            }
+        }
+
+        let alphabet = board.ruleset().alphabet.clone();
+        let mut position = Position::new(board);
+        for (line_number, line) in lines {
+            let Some((key, value)) = line.split_once(':') else {
+                return Err(invalid(
+                    line_number,
+                    "expected 'rack:', 'bag:' or 'scores:'".to_string(),
+                ));
+            };
+            let value = value.trim();
+            match key.trim() {
+                "rack" => position.rack = Some(read_tiles(line_number, value, &alphabet)?),
+                "bag" => position.bag = Some(read_tiles(line_number, value, &alphabet)?),
+                "scores" => {
+                    position.scores = value
+                        .split_whitespace()
+                        .map(|score| {
+                            score.parse().map_err(|_| {
+                                invalid(line_number, format!("'{score}' is not a score"))
+                            })
+                        })
+                        .collect::<Result<_, _>>()?
+                }
+                key => return Err(invalid(line_number, format!("unknown field '{key}'"))),
+            }
+        }
+        Ok(position)
+    }
+}
+
+impl FromStr for Position {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Position::parse(text, Arc::new(Ruleset::default()))
+    }
+}
+
+/// Writes the position in the format read by [`Position::from_str`].
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row_index in 0..self.board.size() {
+            let row: Vec<Letter> = (0..self.board.size())
+                .map(|column_index| self.board.tile(row_index, column_index))
+                .collect();
+            let tiles = self.board.ruleset().alphabet.write_tiles(&row);
+            for (letter, tile) in row.iter().zip(tiles) {
+                match letter {
+                    0 => write!(f, ".")?,
+                    _ => write!(f, "{tile}")?,
+                }
+            }
+            writeln!(f)?;
+        }
+        if let Some(rack) = &self.rack {
+            writeln!(f, "rack: {rack}")?;
+        }
+        if let Some(bag) = &self.bag {
+            writeln!(f, "bag: {bag}")?;
+        }
+        if !self.scores.is_empty() {
+            let scores: Vec<String> = self.scores.iter().map(i32::to_string).collect();
+            writeln!(f, "scores: {}", scores.join(" "))?;
+        }
+        Ok(())
+    }
+}
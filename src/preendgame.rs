@@ -0,0 +1,362 @@
+//! Pre-endgame analysis: when only a few tiles are left in the bag, every
+//! draw can be enumerated instead of sampled.
+//!
+//! For each candidate, every way of splitting the unseen tiles between the
+//! player's draw, the opponent's rack and what stays in the bag is weighed
+//! by its probability. Turns are then played with the best move by equity
+//! until the bag is empty, enumerating the draws again, and the endgame that
+//! follows is searched with both racks known. Candidates are ranked by their
+//! chance of winning the game, then by the spread they lead to on average.
+
+use crate::bag::UnseenTiles;
+use crate::board::Board;
+use crate::endgame::Endgame;
+use crate::error::Error;
+use crate::leave::leave_after;
+use crate::legal_moves::LegalMove;
+use crate::letter::Letter;
+use crate::solver::Solver;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::thread;
+
+/// The settings of a pre-endgame analysis.
+#[derive(Clone, Debug)]
+pub struct Preendgame {
+    /// How many of the moves with the highest equity are analysed.
+    pub candidates: usize,
+    /// The search run on every endgame reached.
+    pub endgame: Endgame,
+    pub threads: usize,
+}
+
+impl Default for Preendgame {
+    fn default() -> Self {
+        Preendgame {
+            candidates: 10,
+            endgame: Endgame { max_plies: 2 },
+            threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+        }
+    }
+}
+
+/// A candidate with the outcome of its analysis.
+#[derive(Clone, Debug)]
+pub struct AnalysedMove {
+    pub legal_move: LegalMove,
+    /// The chance of winning the game, a tie counting as half a win.
+    pub win_probability: f64,
+    /// The points the player to move gains on the opponent by the end of
+    /// the game on average, the candidate's score included.
+    pub expected_spread: f64,
+    /// The number of distinct draws weighed.
+    pub outcomes: usize,
+}
+
+impl Preendgame {
+    /// Analyses the best candidates of `rack` on `board`, the player to move
+    /// leading the opponent by `lead` points, and ranks them best first.
+    /// Fails unless the bag holds from one tile to a full rack.
+    pub fn run(
+        &self,
+        solver: &Solver,
+        board: &Board,
+        rack: &str,
+        lead: i32,
+    ) -> Result<Vec<AnalysedMove>, Error> {
+        let rack_tiles = board.ruleset().alphabet.parse(rack)?;
+        let unseen = UnseenTiles::new(board, &rack_tiles)?;
+        let rack_size = board.ruleset().rack_size;
+        if !(1..=rack_size).contains(&unseen.in_bag()) {
+            return Err(Error::NotPreendgame {
+                in_bag: unseen.in_bag(),
+                rack_size,
+            });
+        }
+        let mut candidates = solver.moves_by_equity_for_tiles(board, &rack_tiles)?;
+        candidates.truncate(self.candidates);
+
+        let threads = self.threads.clamp(1, candidates.len().max(1));
+        let analysis = Analysis {
+            solver,
+            board,
+            rack: &rack_tiles,
+            unseen: &unseen,
+            endgame: &self.endgame,
+            lead,
+        };
+        let mut analysed: Vec<AnalysedMove> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|thread_index| {
+                    let analysis = &analysis;
+                    let candidates = &candidates;
+                    scope.spawn(move || {
+                        candidates
+                            .iter()
+                            .enumerate()
+                            .skip(thread_index)
+                            .step_by(threads)
+                            .map(|(index, candidate)| Ok((index, analysis.candidate(candidate)?)))
+                            .collect::<Result<Vec<_>, Error>>()
+                    })
+                })
+                .collect();
+            let mut analysed = Vec::new();
+            for worker in workers {
+                analysed.extend(worker.join().unwrap()?);
+            }
+            // Candidates tied on both counts stay in order of equity.
+            analysed.sort_by_key(|(index, _)| *index);
+            Ok::<_, Error>(analysed.into_iter().map(|(_, analysed)| analysed).collect())
+        })?;
+        analysed.sort_by(|first, second| {
+            second
+                .win_probability
+                .total_cmp(&first.win_probability)
+                .then(second.expected_spread.total_cmp(&first.expected_spread))
+        });
+        Ok(analysed)
+    }
+}
+
+/// What the analysis of every candidate shares.
+struct Analysis<'a> {
+    solver: &'a Solver,
+    board: &'a Board,
+    rack: &'a [Letter],
+    unseen: &'a UnseenTiles,
+    endgame: &'a Endgame,
+    lead: i32,
+}
+
+/// The running totals of the analysis of a candidate.
+#[derive(Default)]
+struct Outcomes {
+    wins: f64,
+    spread: f64,
+    count: usize,
+    /// The move chosen for a rack on a board, as many draws lead to the
+    /// same turn.
+    chosen: HashMap<(u64, Vec<Letter>), Option<LegalMove>>,
+    /// The endgame spread for the player to move for a board and both
+    /// racks.
+    endgames: HashMap<(u64, Vec<Letter>, Vec<Letter>), i32>,
+}
+
+impl Outcomes {
+    fn add(&mut self, probability: f64, spread: i32, lead: i32) {
+        self.wins += probability
+            * match (lead + spread).signum() {
+                1 => 1.0,
+                0 => 0.5,
+                _ => 0.0,
+            };
+        self.spread += probability * spread as f64;
+        self.count += 1;
+    }
+}
+
+impl Analysis<'_> {
+    fn candidate(&self, candidate: &LegalMove) -> Result<AnalysedMove, Error> {
+        let ruleset = self.board.ruleset();
+        let mut board = self.board.clone();
+        for &(row_index, column_index, tile) in &candidate.placed_tiles {
+            board.set(row_index, column_index, tile);
+        }
+        let leave = leave_after(
+            self.rack,
+            candidate.placed_tiles.iter().map(|(_, _, tile)| *tile),
+        );
+        let unseen = self.unseen.tiles();
+        let opponent_rack_size = ruleset.rack_size.min(unseen.len());
+        let drawn = ruleset
+            .rack_size
+            .saturating_sub(leave.len())
+            .min(unseen.len() - opponent_rack_size);
+        let mut outcomes = Outcomes::default();
+        for (draw, draw_probability, rest) in draws(&unseen, drawn) {
+            for (opponent_rack, rack_probability, bag) in draws(&rest, opponent_rack_size) {
+                let rack = [leave.as_slice(), &draw].concat();
+                let line = Line {
+                    spread: -(candidate.score as i32),
+                    probability: draw_probability * rack_probability,
+                    sign: -1,
+                    passed: false,
+                };
+                self.play_out(&mut board, [opponent_rack, rack], bag, line, &mut outcomes)?;
+            }
+        }
+        Ok(AnalysedMove {
+            legal_move: candidate.clone(),
+            win_probability: outcomes.wins,
+            expected_spread: outcomes.spread,
+            outcomes: outcomes.count,
+        })
+    }
+
+    /// Plays the turns left until the bag is empty, then the endgame,
+    /// `racks` starting with the rack of the player to move.
+    fn play_out(
+        &self,
+        board: &mut Board,
+        racks: [Vec<Letter>; 2],
+        bag: Vec<Letter>,
+        line: Line,
+        outcomes: &mut Outcomes,
+    ) -> Result<(), Error> {
+        let ruleset = board.shared_ruleset();
+        let [mut rack, mut other_rack] = racks;
+        rack.sort_unstable();
+        other_rack.sort_unstable();
+        let mut hasher = DefaultHasher::new();
+        board.primary.hash(&mut hasher);
+        let board_key = hasher.finish();
+        if bag.is_empty() {
+            let key = (board_key, rack, other_rack);
+            let spread = match outcomes.endgames.get(&key) {
+                Some(spread) => *spread,
+                None => {
+                    let solution =
+                        self.endgame
+                            .solve_for_tiles(self.solver, board, &key.1, &key.2)?;
+                    outcomes.endgames.insert(key, solution.spread);
+                    solution.spread
+                }
+            };
+            outcomes.add(
+                line.probability,
+                line.sign * (line.spread + spread),
+                self.lead,
+            );
+            return Ok(());
+        }
+        let key = (board_key, rack.clone());
+        let best = match outcomes.chosen.get(&key) {
+            Some(best) => best.clone(),
+            None => {
+                let best = self
+                    .solver
+                    .moves_by_equity_for_tiles(board, &rack)?
+                    .into_iter()
+                    .next();
+                outcomes.chosen.insert(key, best.clone());
+                best
+            }
+        };
+        let Some(best) = best else {
+            if line.passed {
+                // Two passes in a row end the game, each player losing the
+                // value of their rack.
+                let value = |rack: &[Letter]| -> i32 {
+                    rack.iter()
+                        .map(|tile| ruleset.letter_value(*tile) as i32)
+                        .sum()
+                };
+                let spread = line.spread + value(&other_rack) - value(&rack);
+                outcomes.add(line.probability, line.sign * spread, self.lead);
+                return Ok(());
+            }
+            // Passing draws nothing: the opponent plays on with the same bag.
+            let line = Line {
+                spread: -line.spread,
+                sign: -line.sign,
+                passed: true,
+                ..line
+            };
+            return self.play_out(board, [other_rack, rack], bag, line, outcomes);
+        };
+        for &(row_index, column_index, tile) in &best.placed_tiles {
+            board.set(row_index, column_index, tile);
+        }
+        let leave = leave_after(&rack, best.placed_tiles.iter().map(|(_, _, tile)| *tile));
+        let drawn = best.placed_tiles.len().min(bag.len());
+        for (draw, draw_probability, rest) in draws(&bag, drawn) {
+            let next = Line {
+                spread: -(line.spread + best.score as i32),
+                probability: line.probability * draw_probability,
+                sign: -line.sign,
+                passed: false,
+            };
+            let racks = [other_rack.clone(), [leave.as_slice(), &draw].concat()];
+            self.play_out(board, racks, rest, next, outcomes)?;
+        }
+        for &(row_index, column_index, _) in &best.placed_tiles {
+            board.set(row_index, column_index, 0);
+        }
+        Ok(())
+    }
+}
+
+/// Where a line of play stands, seen from the player to move.
+#[derive(Clone, Copy)]
+struct Line {
+    /// The points the player to move has gained on the opponent so far.
+    spread: i32,
+    /// The chance of the draws leading here.
+    probability: f64,
+    /// 1 if the player to move is the one the analysis is for, -1 otherwise.
+    sign: i32,
+    /// Whether the previous turn was a pass.
+    passed: bool,
+}
+
+/// Every distinct set of `count` tiles that can be drawn from `tiles`, with
+/// its probability and the tiles left.
+fn draws(tiles: &[Letter], count: usize) -> Vec<(Vec<Letter>, f64, Vec<Letter>)> {
+    let mut kinds: Vec<(Letter, usize)> = Vec::new();
+    for tile in tiles {
+        match kinds.iter_mut().find(|(kind, _)| kind == tile) {
+            Some((_, available)) => *available += 1,
+            None => kinds.push((*tile, 1)),
+        }
+    }
+    let mut draws = Vec::new();
+    let total = binomial(tiles.len(), count);
+    let mut taken = vec![0; kinds.len()];
+    enumerate_draws(&kinds, 0, count, &mut taken, &mut |taken: &[usize]| {
+        let mut draw = Vec::new();
+        let mut rest = Vec::new();
+        let mut ways = 1.0;
+        for ((tile, available), taken) in kinds.iter().zip(taken) {
+            draw.extend(std::iter::repeat_n(*tile, *taken));
+            rest.extend(std::iter::repeat_n(*tile, available - taken));
+            ways *= binomial(*available, *taken);
+        }
+        draws.push((draw, ways / total, rest));
+    });
+    draws
+}
+
+/// Calls `visit` with every way of taking `count` tiles from `kinds`, as
+/// the number taken of each kind from `index` on.
+fn enumerate_draws(
+    kinds: &[(Letter, usize)],
+    index: usize,
+    count: usize,
+    taken: &mut Vec<usize>,
+    visit: &mut impl FnMut(&[usize]),
+) {
+    if index == kinds.len() {
+        if count == 0 {
+            visit(taken);
+        }
+        return;
+    }
+    let left_after: usize = kinds[index + 1..]
+        .iter()
+        .map(|(_, available)| available)
+        .sum();
+    let minimum = count.saturating_sub(left_after);
+    for number in minimum..=kinds[index].1.min(count) {
+        taken[index] = number;
+        enumerate_draws(kinds, index + 1, count - number, taken, visit);
+    }
+    taken[index] = 0;
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    (0..k).fold(1.0, |ways, index| {
+        ways * (n - index) as f64 / (index + 1) as f64
+    })
+}
@@ -0,0 +1,42 @@
+//! A small seeded random number generator, so that simulations and games
+//! can be replayed from their seed.
+
+/// SplitMix64, which is fast and good enough to shuffle tiles.
+#[derive(Clone, Debug)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        Random { state: seed }
+    }
+
+    /// A generator for the `stream`-th of several independent tasks
+    /// sharing a seed, such as the iterations of a simulation.
+    pub fn for_stream(seed: u64, stream: u64) -> Random {
+        let mut random = Random::new(seed ^ stream.wrapping_mul(0xD1B5_4A32_D192_ED03));
+        random.next_u64();
+        random
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut value = self.state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        value ^ (value >> 31)
+    }
+
+    /// A number from 0 to `bound` excluded, `bound` being at least 1.
+    pub fn below(&mut self, bound: usize) -> usize {
+        ((self.next_u64() as u128 * bound as u128) >> 64) as usize
+    }
+
+    /// Puts `items` in a random order.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for index in (1..items.len()).rev() {
+            items.swap(index, self.below(index + 1));
+        }
+    }
+}
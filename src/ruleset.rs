@@ -0,0 +1,468 @@
+//! The rules that change from one edition of the game to another: the tiles,
+//! their values, the premium squares, the bingo bonus and the rack size.
+//!
+//! Besides the built-in presets, a ruleset can be read from a file made of
+//! one setting per line:
+//!
+//! ```text
+//! # Comments start with '#'.
+//! name English
+//! rack_size 7
+//! bingo_bonus 50
+//! # The letters in order, A to Z when not given.
+//! alphabet A B C D E F G H I J K L M N O P Q R S T U V W X Y Z
+//! # tile LETTER COUNT VALUE, '?' being the blank.
+//! tile A 9 1
+//! tile B 2 3
+//! tile ? 2 0
+//! # One line per row of the board: '.' for a normal square, 'd', 't' and
+//! # 'q' for double, triple and quadruple letter, 'D', 'T' and 'Q' for
+//! # double, triple and quadruple word.
+//! layout T..d...T...d..T
+//! ```
+//!
+//! Letters of the alphabet without a `tile` line can only be played with a
+//! blank, and the standard 15x15 layout is used when no `layout` line is
+//! given. Otherwise the layout gives the size of the board: it must have as
+//! many rows as squares per row, at most 26 so that columns can be named A
+//! to Z.
+
+use crate::alphabet::Alphabet;
+use crate::error::Error;
+use crate::grid::Grid;
+use crate::letter::{Blank, Letter, BLANK};
+use crate::score::SquareType;
+use std::fs;
+use std::str::FromStr;
+
+/// The largest board a layout can describe, its columns being named A to Z.
+pub const MAX_BOARD_SIZE: usize = 26;
+
+/// The premium squares shared by the usual editions of the game.
+const STANDARD_LAYOUT: [&str; 15] = [
+    "T..d...T...d..T",
+    ".D...t...t...D.",
+    "..D...d.d...D..",
+    "d..D...d...D..d",
+    "....D.....D....",
+    ".t...t...t...t.",
+    "..d...d.d...d..",
+    "T..d...D...d..T",
+    "..d...d.d...d..",
+    ".t...t...t...t.",
+    "....D.....D....",
+    "d..D...d...D..d",
+    "..D...d.d...D..",
+    ".D...t...t...D.",
+    "T..d...T...d..T",
+];
+
+/// `(letter, count, value)` for every tile of the French edition.
+const FRENCH_TILES: &[(&str, u8, u16)] = &[
+    ("A", 9, 1),
+    ("B", 2, 3),
+    ("C", 2, 3),
+    ("D", 3, 2),
+    ("E", 15, 1),
+    ("F", 2, 4),
+    ("G", 2, 2),
+    ("H", 2, 4),
+    ("I", 8, 1),
+    ("J", 1, 8),
+    ("K", 1, 10),
+    ("L", 5, 1),
+    ("M", 3, 2),
+    ("N", 6, 1),
+    ("O", 6, 1),
+    ("P", 2, 3),
+    ("Q", 1, 8),
+    ("R", 6, 1),
+    ("S", 6, 1),
+    ("T", 6, 1),
+    ("U", 6, 1),
+    ("V", 2, 4),
+    ("W", 1, 10),
+    ("X", 1, 10),
+    ("Y", 1, 10),
+    ("Z", 1, 10),
+    ("?", 2, 0),
+];
+
+/// The tiles of the English edition, played with both the TWL and Collins
+/// word lists.
+const ENGLISH_TILES: &[(&str, u8, u16)] = &[
+    ("A", 9, 1),
+    ("B", 2, 3),
+    ("C", 2, 3),
+    ("D", 4, 2),
+    ("E", 12, 1),
+    ("F", 2, 4),
+    ("G", 3, 2),
+    ("H", 2, 4),
+    ("I", 9, 1),
+    ("J", 1, 8),
+    ("K", 1, 5),
+    ("L", 4, 1),
+    ("M", 2, 3),
+    ("N", 6, 1),
+    ("O", 8, 1),
+    ("P", 2, 3),
+    ("Q", 1, 10),
+    ("R", 6, 1),
+    ("S", 4, 1),
+    ("T", 6, 1),
+    ("U", 4, 1),
+    ("V", 2, 4),
+    ("W", 2, 4),
+    ("X", 1, 8),
+    ("Y", 2, 4),
+    ("Z", 1, 10),
+    ("?", 2, 0),
+];
+
+/// The tiles of the Spanish edition, in the [`Alphabet::spanish`] alphabet.
+const SPANISH_TILES: &[(&str, u8, u16)] = &[
+    ("A", 12, 1),
+    ("B", 2, 3),
+    ("C", 4, 3),
+    ("CH", 1, 5),
+    ("D", 5, 2),
+    ("E", 12, 1),
+    ("F", 1, 4),
+    ("G", 2, 2),
+    ("H", 2, 4),
+    ("I", 6, 1),
+    ("J", 1, 8),
+    ("L", 4, 1),
+    ("LL", 1, 8),
+    ("M", 2, 3),
+    ("N", 5, 1),
+    ("Ñ", 1, 8),
+    ("O", 9, 1),
+    ("P", 2, 3),
+    ("Q", 1, 5),
+    ("R", 5, 1),
+    ("RR", 1, 8),
+    ("S", 6, 1),
+    ("T", 4, 1),
+    ("U", 5, 1),
+    ("V", 1, 4),
+    ("X", 1, 8),
+    ("Y", 1, 4),
+    ("Z", 1, 10),
+    ("?", 2, 0),
+];
+
+/// The tiles of the German edition, in the [`Alphabet::german`] alphabet.
+const GERMAN_TILES: &[(&str, u8, u16)] = &[
+    ("A", 5, 1),
+    ("B", 2, 3),
+    ("C", 2, 4),
+    ("D", 4, 1),
+    ("E", 15, 1),
+    ("F", 2, 4),
+    ("G", 3, 2),
+    ("H", 4, 2),
+    ("I", 6, 1),
+    ("J", 1, 6),
+    ("K", 2, 4),
+    ("L", 3, 2),
+    ("M", 4, 3),
+    ("N", 9, 1),
+    ("O", 3, 2),
+    ("P", 1, 4),
+    ("Q", 1, 10),
+    ("R", 6, 1),
+    ("S", 7, 1),
+    ("T", 6, 1),
+    ("U", 6, 1),
+    ("V", 1, 6),
+    ("W", 1, 3),
+    ("X", 1, 8),
+    ("Y", 1, 10),
+    ("Z", 1, 3),
+    ("Ä", 1, 6),
+    ("Ö", 1, 8),
+    ("Ü", 1, 6),
+    ("?", 2, 0),
+];
+
+/// The names accepted by [`Ruleset::preset`].
+pub const PRESETS: [&str; 5] = ["french", "twl", "collins", "spanish", "german"];
+
+/// The settings of an edition of the game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ruleset {
+    pub name: String,
+    /// The letters of the game and how they are written.
+    pub alphabet: Alphabet,
+    /// The value of each letter, indexed by letter, index 0 standing for
+    /// the blank.
+    values: Vec<u16>,
+    /// The number of tiles of each letter in the bag at the start of the
+    /// game, indexed like `values`.
+    counts: Vec<u8>,
+    /// The premium of every square, which also gives the size of the board.
+    pub premium_squares: Grid<SquareType>,
+    /// The points added when a move uses the whole rack.
+    pub bingo_bonus: u16,
+    pub rack_size: usize,
+}
+
+fn square_type(symbol: char) -> Option<SquareType> {
+    match symbol {
+        '.' => Some(SquareType::Normal),
+        'd' => Some(SquareType::DoubleLetter),
+        't' => Some(SquareType::TripleLetter),
+        'D' => Some(SquareType::DoubleWord),
+        'T' => Some(SquareType::TripleWord),
+        'q' => Some(SquareType::QuadrupleLetter),
+        'Q' => Some(SquareType::QuadrupleWord),
+        _ => None,
+    }
+}
+
+/// Reads one row of a layout, `None` if it holds an unknown symbol.
+fn parse_layout_row(row: &str) -> Option<Vec<SquareType>> {
+    row.chars().map(square_type).collect()
+}
+
+fn standard_layout() -> Grid<SquareType> {
+    let rows = STANDARD_LAYOUT
+        .iter()
+        .map(|text| parse_layout_row(text).unwrap())
+        .collect();
+    Grid::from_rows(rows).unwrap()
+}
+
+impl Ruleset {
+    /// The number of rows of the board, which is also its number of columns.
+    pub fn board_size(&self) -> usize {
+        self.premium_squares.size()
+    }
+
+    /// A ruleset with the usual settings but no tiles.
+    fn empty(name: &str, alphabet: Alphabet) -> Ruleset {
+        let table_size = alphabet.letter_count() + 1;
+        Ruleset {
+            name: name.to_string(),
+            alphabet,
+            values: vec![0; table_size],
+            counts: vec![0; table_size],
+            premium_squares: standard_layout(),
+            bingo_bonus: 50,
+            rack_size: 7,
+        }
+    }
+
+    fn from_tiles(name: &str, alphabet: Alphabet, tiles: &[(&str, u8, u16)]) -> Ruleset {
+        let mut ruleset = Ruleset::empty(name, alphabet);
+        for &(token, count, value) in tiles {
+            let index = tile_index(ruleset.alphabet.read_tile(token).unwrap().0);
+            ruleset.counts[index] = count;
+            ruleset.values[index] = value;
+        }
+        ruleset
+    }
+
+    /// The French edition, played with the ODS word list.
+    pub fn french() -> Ruleset {
+        Ruleset::from_tiles("French", Alphabet::latin(), FRENCH_TILES)
+    }
+
+    /// The English edition as played in North America with the TWL word list.
+    pub fn twl() -> Ruleset {
+        Ruleset::from_tiles("English (TWL)", Alphabet::latin(), ENGLISH_TILES)
+    }
+
+    /// The English edition as played elsewhere with the Collins word list.
+    pub fn collins() -> Ruleset {
+        Ruleset::from_tiles("English (Collins)", Alphabet::latin(), ENGLISH_TILES)
+    }
+
+    pub fn spanish() -> Ruleset {
+        Ruleset::from_tiles("Spanish", Alphabet::spanish(), SPANISH_TILES)
+    }
+
+    pub fn german() -> Ruleset {
+        Ruleset::from_tiles("German", Alphabet::german(), GERMAN_TILES)
+    }
+
+    /// A built-in ruleset by name, one of [`PRESETS`].
+    pub fn preset(name: &str) -> Option<Ruleset> {
+        match name {
+            "french" => Some(Ruleset::french()),
+            "twl" => Some(Ruleset::twl()),
+            "collins" => Some(Ruleset::collins()),
+            "spanish" => Some(Ruleset::spanish()),
+            "german" => Some(Ruleset::german()),
+            _ => None,
+        }
+    }
+
+    /// Reads a ruleset file.
+    pub fn load(path: &str) -> Result<Ruleset, Error> {
+        let text = fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        text.parse()
+    }
+
+    /// The points a tile is worth. Played blanks are worth as much as a
+    /// blank on the rack, whatever letter they stand for.
+    pub fn letter_value(&self, letter: Letter) -> u16 {
+        match letter {
+            0 => 0,
+            _ => self.values[tile_index(letter)],
+        }
+    }
+
+    /// The number of tiles of a letter in a full bag, [`BLANK`] standing
+    /// for the blanks.
+    pub fn tile_count(&self, letter: Letter) -> u8 {
+        self.counts[tile_index(letter)]
+    }
+
+    /// The number of tiles in a full bag.
+    pub fn tile_total(&self) -> usize {
+        self.counts.iter().map(|count| *count as usize).sum()
+    }
+
+    /// Whether tiles can be exchanged with `in_bag` tiles left in the bag:
+    /// at least a full rack must be left.
+    pub fn can_exchange(&self, in_bag: usize) -> bool {
+        in_bag >= self.rack_size
+    }
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Ruleset::french()
+    }
+}
+
+/// The index of a tile in the value and count tables.
+pub(crate) fn tile_index(letter: Letter) -> usize {
+    if letter.is_blank() {
+        0
+    } else {
+        letter as usize
+    }
+}
+
+impl FromStr for Ruleset {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = |line: usize, message: String| Error::InvalidRuleset { line, message };
+        let mut name = String::new();
+        let mut alphabet = Alphabet::latin();
+        let mut tiles = Vec::new();
+        let mut rack_size = 7;
+        let mut bingo_bonus = 50;
+        let mut layout = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let rest = rest.trim();
+            let number = |text: &str| {
+                text.parse::<u16>()
+                    .map_err(|_| invalid(line_number, format!("'{text}' is not a number")))
+            };
+            match keyword {
+                "name" => name = rest.to_string(),
+                "rack_size" => rack_size = number(rest)? as usize,
+                "bingo_bonus" => bingo_bonus = number(rest)?,
+                "alphabet" => {
+                    let tokens: Vec<_> = rest.split_whitespace().collect();
+                    alphabet =
+                        Alphabet::new(&tokens).map_err(|message| invalid(line_number, message))?;
+                }
+                "tile" => {
+                    let [letter, count, value] = rest.split_whitespace().collect::<Vec<_>>()[..]
+                    else {
+                        return Err(invalid(
+                            line_number,
+                            "expected 'tile LETTER COUNT VALUE'".to_string(),
+                        ));
+                    };
+                    let count = u8::try_from(number(count)?)
+                        .map_err(|_| invalid(line_number, format!("{count} tiles are too many")))?;
+                    // Letters are read once the alphabet is known, as it may
+                    // be given after them.
+                    tiles.push((line_number, letter, count, number(value)?));
+                }
+                "layout" => {
+                    let row = parse_layout_row(rest)
+                        .filter(|row| (1..=MAX_BOARD_SIZE).contains(&row.len()))
+                        .ok_or_else(|| {
+                            invalid(
+                                line_number,
+                                format!(
+                                    "a layout row holds 1 to {MAX_BOARD_SIZE} squares among \
+                                     '.', 'd', 't', 'q', 'D', 'T' and 'Q'"
+                                ),
+                            )
+                        })?;
+                    layout.push((line_number, row));
+                }
+                _ => return Err(invalid(line_number, format!("unknown setting '{keyword}'"))),
+            }
+        }
+        if tiles.is_empty() {
+            return Err(invalid(
+                text.lines().count(),
+                "no tile is given".to_string(),
+            ));
+        }
+        let mut ruleset = Ruleset::empty(&name, alphabet);
+        ruleset.rack_size = rack_size;
+        ruleset.bingo_bonus = bingo_bonus;
+        for (line_number, letter, count, value) in tiles {
+            let tile = ruleset
+                .alphabet
+                .read_tile(letter)
+                .filter(|&(tile, length)| {
+                    length == letter.len() && (tile == BLANK || !tile.is_blank())
+                })
+                .ok_or_else(|| {
+                    invalid(
+                        line_number,
+                        format!("'{letter}' is not a letter of the alphabet"),
+                    )
+                })?
+                .0;
+            ruleset.counts[tile_index(tile)] = count;
+            ruleset.values[tile_index(tile)] = value;
+        }
+        if ruleset.rack_size == 0 {
+            return Err(invalid(
+                text.lines().count(),
+                "the rack size must be at least 1".to_string(),
+            ));
+        }
+        if let Some((_, first_row)) = layout.first() {
+            let size = first_row.len();
+            if let Some((line, row)) = layout.iter().find(|(_, row)| row.len() != size) {
+                return Err(invalid(
+                    *line,
+                    format!("this row holds {} squares instead of {size}", row.len()),
+                ));
+            }
+            if layout.len() != size {
+                return Err(invalid(
+                    layout.last().unwrap().0,
+                    format!("the layout has {} rows instead of {size}", layout.len()),
+                ));
+            }
+            ruleset.premium_squares =
+                Grid::from_rows(layout.into_iter().map(|(_, row)| row).collect()).unwrap();
+        }
+        Ok(ruleset)
+    }
+}
@@ -1,102 +1,189 @@
-use crate::board::Board;
-use crate::letter::{FromChar, Letter};
-
-const LETTERS_VALUES: [u16; 27] = [
-    0, 1, 3, 3, 2, 1, 4, 2, 4, 1, 8, 10, 1, 2, 1, 1, 3, 8, 1, 1, 1, 1, 4, 10, 10, 10, 10,
-];
+//! Premium squares and the scoring of moves.
 
-pub const PREMIUM_SQUARES: [[u8; 15]; 15] = [
-    [4, 0, 0, 1, 0, 0, 0, 4, 0, 0, 0, 1, 0, 0, 4],
-    [0, 3, 0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 3, 0],
-    [0, 0, 3, 0, 0, 0, 1, 0, 1, 0, 0, 0, 3, 0, 0],
-    [1, 0, 0, 3, 0, 0, 0, 1, 0, 0, 0, 3, 0, 0, 1],
-    [0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0],
-    [0, 2, 0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 2, 0],
-    [0, 0, 1, 0, 0, 0, 1, 0, 1, 0, 0, 0, 1, 0, 0],
-    [4, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 1, 0, 0, 4],
-    [0, 0, 1, 0, 0, 0, 1, 0, 1, 0, 0, 0, 1, 0, 0],
-    [0, 2, 0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 2, 0],
-    [0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0],
-    [1, 0, 0, 3, 0, 0, 0, 1, 0, 0, 0, 3, 0, 0, 1],
-    [0, 0, 3, 0, 0, 0, 1, 0, 1, 0, 0, 0, 3, 0, 0],
-    [0, 3, 0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 3, 0],
-    [4, 0, 0, 1, 0, 0, 0, 4, 0, 0, 0, 1, 0, 0, 4],
-];
+use crate::board::Board;
+use crate::letter::Letter;
+use crate::ruleset::Ruleset;
 
-enum SquareType {
+/// The bonus a square gives to the tile placed on it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SquareType {
     Normal,
     DoubleLetter,
     TripleLetter,
     DoubleWord,
     TripleWord,
+    QuadrupleLetter,
+    QuadrupleWord,
+}
+
+/// Sums the values of the tiles in `letters`. Empty squares are worth
+/// nothing.
+pub fn word_value(ruleset: &Ruleset, letters: &[Letter]) -> u16 {
+    letters
+        .iter()
+        .map(|letter| ruleset.letter_value(*letter))
+        .sum()
 }
 
 impl SquareType {
-    fn from_u8(value: u8) -> Self {
-        match value {
-            0 => Self::Normal,
-            1 => Self::DoubleLetter,
-            2 => Self::TripleLetter,
-            3 => Self::DoubleWord,
-            4 => Self::TripleWord,
-            _ => panic!("Couldn't convert value to SquareType"),
+    /// The factor applied to the tile placed on the square.
+    pub fn letter_multiplier(self) -> u16 {
+        match self {
+            SquareType::DoubleLetter => 2,
+            SquareType::TripleLetter => 3,
+            SquareType::QuadrupleLetter => 4,
+            _ => 1,
         }
     }
+
+    /// The factor applied to every word going through the square.
+    pub fn word_multiplier(self) -> u16 {
+        match self {
+            SquareType::DoubleWord => 2,
+            SquareType::TripleWord => 3,
+            SquareType::QuadrupleWord => 4,
+            _ => 1,
+        }
+    }
+}
+
+/// A square of a scored word.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScoredSquare {
+    pub row_index: usize,
+    pub column_index: usize,
+    pub letter: Letter,
+    /// Whether the tile is placed by the move. Premiums only count for
+    /// those.
+    pub is_new: bool,
+    pub premium: SquareType,
+    /// The value of the tile, its letter multiplier applied.
+    pub value: u16,
 }
 
-pub fn word_value(letters: &str) -> u16 {
-    let mut score = 0;
-    for letter in letters.chars() {
-        score += LETTERS_VALUES[Letter::from_char(letter) as usize];
+/// The score of one of the words formed by a move.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WordScore {
+    /// The word, blanks in lowercase.
+    pub word: String,
+    pub across: bool,
+    pub squares: Vec<ScoredSquare>,
+    /// The product of the word multipliers under the new tiles.
+    pub word_multiplier: u16,
+    pub score: u16,
+}
+
+/// How the score of a move adds up: the main word first, then every cross
+/// word, then the bingo bonus.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScoreBreakdown {
+    pub words: Vec<WordScore>,
+    pub bingo_bonus: u16,
+}
+
+impl ScoreBreakdown {
+    pub fn total(&self) -> u16 {
+        self.words.iter().map(|word| word.score).sum::<u16>() + self.bingo_bonus
+    }
+}
+
+/// Scores a word made of the squares `(row_index, column_index)` of
+/// `board.primary`, `letters` giving the tile of each, whether new or not.
+fn score_word(
+    board: &Board,
+    squares: impl Iterator<Item = (usize, usize)>,
+    letters: &[Letter],
+    along_rows: bool,
+) -> WordScore {
+    let ruleset = board.ruleset();
+    let mut word_multiplier = 1;
+    let squares: Vec<ScoredSquare> = squares
+        .zip(letters)
+        .map(|((row_index, column_index), &letter)| {
+            let is_new = board.primary[(row_index, column_index)] == 0;
+            let premium = board.premium(row_index, column_index);
+            let mut value = ruleset.letter_value(letter);
+            if is_new {
+                value *= premium.letter_multiplier();
+                word_multiplier *= premium.word_multiplier();
+            }
+            let (row_index, column_index) = if board.across {
+                (row_index, column_index)
+            } else {
+                (column_index, row_index)
+            };
+            ScoredSquare {
+                row_index,
+                column_index,
+                letter,
+                is_new,
+                premium,
+                value,
+            }
+        })
+        .collect();
+    let score = squares.iter().map(|square| square.value).sum::<u16>() * word_multiplier;
+    WordScore {
+        word: ruleset.alphabet.write(letters),
+        across: along_rows == board.across,
+        squares,
+        word_multiplier,
+        score,
     }
-    score
 }
 
-pub fn calculate_score(
+/// Scores `letters` played along the row `row_index` of `board.primary` from
+/// `column_index`, `letters` holding the whole word, tiles already on the
+/// board included. Every word formed is scored on its own: the main word
+/// and, for each new tile, the word it forms across the main one.
+pub fn score_move(
     board: &Board,
-    value_set: &[[u16; 15]; 15],
-    letters: &str,
+    letters: &[Letter],
     row_index: usize,
-    mut column_index: usize,
+    column_index: usize,
     bingo: bool,
-) -> u16 {
-    let mut score = 0;
-    let mut bonus_score = 0;
-    let mut coefficient = 1;
-    for letter in letters.chars() {
-        let mut letter_value = LETTERS_VALUES[Letter::from_char(letter) as usize];
-        let mut bonus_coefficient = 1;
-        if board.primary[row_index][column_index] == 0 {
-            match SquareType::from_u8(PREMIUM_SQUARES[row_index][column_index]) {
-                SquareType::DoubleLetter => {
-                    letter_value *= 2;
-                }
-                SquareType::TripleLetter => {
-                    letter_value *= 3;
-                }
-                SquareType::DoubleWord => {
-                    coefficient *= 2;
-                    bonus_coefficient = 2;
-                }
-                SquareType::TripleWord => {
-                    coefficient *= 3;
-                    bonus_coefficient = 3;
-                }
-                _ => (),
-            }
+) -> ScoreBreakdown {
+    let mut breakdown = ScoreBreakdown {
+        words: Vec::new(),
+        bingo_bonus: if bingo {
+            board.ruleset().bingo_bonus
         } else {
-            
+            0
+        },
+    };
+    if letters.len() > 1 {
+        breakdown.words.push(score_word(
+            board,
+            (column_index..).map(|column| (row_index, column)),
+            letters,
+            true,
+        ));
+    }
+    for (index, &letter) in letters.iter().enumerate() {
+        let column = column_index + index;
+        if board.primary[(row_index, column)] != 0 {
+            continue;
         }
-        score += letter_value;
-        if value_set[row_index][column_index] != 0 {
-            bonus_score += value_set[row_index][column_index] * bonus_coefficient;
-            bonus_score += letter_value * bonus_coefficient;
+        let line = board.secondary.row(column);
+        let mut start = row_index;
+        while start > 0 && line[start - 1] != 0 {
+            start -= 1;
         }
-        column_index += 1;
-    }
-    score *= coefficient;
-    if bingo {
-        score += 50
+        let mut end = row_index;
+        while end + 1 < line.len() && line[end + 1] != 0 {
+            end += 1;
+        }
+        if start == end {
+            continue;
+        }
+        let mut cross_letters = line[start..=end].to_vec();
+        cross_letters[row_index - start] = letter;
+        breakdown.words.push(score_word(
+            board,
+            (start..=end).map(|row| (row, column)),
+            &cross_letters,
+            false,
+        ));
     }
-    score + bonus_score
+    breakdown
 }
@@ -0,0 +1,225 @@
+//! Monte Carlo simulation: the best candidates are each played out a few
+//! turns ahead against many random draws, and ranked by the average spread
+//! they lead to rather than by their score alone.
+//!
+//! Every iteration draws a rack for the opponent and refills from the
+//! unseen tiles, then both players take turns with a simple strategy. All
+//! candidates are played out against the same draws for a given iteration,
+//! which makes their averages comparable with fewer iterations, and each
+//! iteration is seeded from the seed of the simulation and its number, so
+//! that results do not depend on the number of threads.
+
+use crate::bag::UnseenTiles;
+use crate::board::Board;
+use crate::error::Error;
+use crate::leave::leave_after;
+use crate::legal_moves::LegalMove;
+use crate::letter::Letter;
+use crate::random::Random;
+use crate::solver::Solver;
+use std::thread;
+
+/// How the players choose their moves while a candidate is played out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// The highest-scoring move.
+    Greedy,
+    /// The move with the highest equity.
+    Equity,
+}
+
+/// The settings of a simulation.
+#[derive(Clone, Debug)]
+pub struct Simulation {
+    /// How many of the moves with the highest equity are simulated.
+    pub candidates: usize,
+    /// How many random draws each candidate is played out against.
+    pub iterations: usize,
+    /// How many turns are played after the candidate, the opponent's reply
+    /// being the first.
+    pub plies: usize,
+    pub strategy: Strategy,
+    pub seed: u64,
+    pub threads: usize,
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Simulation {
+            candidates: 10,
+            iterations: 100,
+            plies: 2,
+            strategy: Strategy::Equity,
+            seed: 0,
+            threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+        }
+    }
+}
+
+/// A candidate with the outcome of its simulation.
+#[derive(Clone, Debug)]
+pub struct SimulatedMove {
+    pub legal_move: LegalMove,
+    /// The points the player to move ends up ahead of the opponent on
+    /// average, the candidate's score included.
+    pub average_spread: f64,
+    pub iterations: usize,
+}
+
+impl Simulation {
+    /// Simulates the best candidates of `rack` on `board` and ranks them by
+    /// average spread, best first.
+    pub fn run(
+        &self,
+        solver: &Solver,
+        board: &Board,
+        rack: &str,
+    ) -> Result<Vec<SimulatedMove>, Error> {
+        self.run_for_tiles(solver, board, &board.ruleset().alphabet.parse(rack)?)
+    }
+
+    /// Simulates the best candidates like [`Simulation::run`] for a rack
+    /// given as tiles, [`BLANK`](crate::letter::BLANK) standing for a blank.
+    pub fn run_for_tiles(
+        &self,
+        solver: &Solver,
+        board: &Board,
+        rack_tiles: &[Letter],
+    ) -> Result<Vec<SimulatedMove>, Error> {
+        let unseen = UnseenTiles::new(board, rack_tiles)?;
+        let mut candidates = solver.moves_by_equity_for_tiles(board, rack_tiles)?;
+        candidates.truncate(self.candidates);
+        if candidates.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let threads = self.threads.clamp(1, self.iterations.max(1));
+        let playout = Playout {
+            solver,
+            board,
+            rack: rack_tiles,
+            unseen: &unseen,
+            simulation: self,
+        };
+        // Spreads are summed as integers so that the order in which the
+        // threads finish does not change the result.
+        let totals: Vec<Vec<i64>> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|thread_index| {
+                    let playout = &playout;
+                    let candidates = &candidates;
+                    scope.spawn(move || {
+                        let mut totals = vec![0; candidates.len()];
+                        for iteration in (thread_index..self.iterations).step_by(threads) {
+                            for (total, candidate) in totals.iter_mut().zip(candidates) {
+                                *total += playout.spread(candidate, iteration as u64);
+                            }
+                        }
+                        totals
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .collect()
+        });
+
+        let mut simulated: Vec<SimulatedMove> = candidates
+            .into_iter()
+            .enumerate()
+            .map(|(index, legal_move)| {
+                let total: i64 = totals.iter().map(|totals| totals[index]).sum();
+                SimulatedMove {
+                    legal_move,
+                    average_spread: total as f64 / self.iterations.max(1) as f64,
+                    iterations: self.iterations,
+                }
+            })
+            .collect();
+        simulated.sort_by(|first, second| second.average_spread.total_cmp(&first.average_spread));
+        Ok(simulated)
+    }
+}
+
+/// What every iteration of a simulation shares.
+struct Playout<'a> {
+    solver: &'a Solver,
+    board: &'a Board,
+    rack: &'a [Letter],
+    unseen: &'a UnseenTiles,
+    simulation: &'a Simulation,
+}
+
+impl Playout<'_> {
+    /// Plays `candidate` then the following turns against the draws of
+    /// `iteration`, returning the spread of the player to move.
+    fn spread(&self, candidate: &LegalMove, iteration: u64) -> i64 {
+        let mut random = Random::for_stream(self.simulation.seed, iteration);
+        let ruleset = self.board.ruleset();
+        let mut bag = self.unseen.tiles();
+        random.shuffle(&mut bag);
+        let opponent_rack_size = ruleset.rack_size.min(bag.len());
+        let mut racks = [
+            self.rack.to_vec(),
+            bag.split_off(bag.len() - opponent_rack_size),
+        ];
+        let mut board = self.board.clone();
+
+        let mut spread = 0;
+        let mut passes = 0;
+        let mut player = 0;
+        let mut next_move = Some(candidate.clone());
+        for ply in 0..=self.simulation.plies {
+            if ply > 0 {
+                next_move = self.choose(&board, &racks[player]);
+            }
+            let sign = if player == 0 { 1 } else { -1 };
+            match next_move.take() {
+                Some(legal_move) => {
+                    passes = 0;
+                    spread += sign * legal_move.score as i64;
+                    for &(row_index, column_index, tile) in &legal_move.placed_tiles {
+                        board.set(row_index, column_index, tile);
+                    }
+                    let rack = &mut racks[player];
+                    *rack = leave_after(
+                        rack,
+                        legal_move.placed_tiles.iter().map(|(_, _, tile)| *tile),
+                    );
+                    let drawn = ruleset.rack_size.saturating_sub(rack.len()).min(bag.len());
+                    rack.extend(bag.drain(bag.len() - drawn..));
+                    if rack.is_empty() {
+                        // Going out ends the game, with the value of the
+                        // other rack counted twice.
+                        let left: i64 = racks[1 - player]
+                            .iter()
+                            .map(|tile| ruleset.letter_value(*tile) as i64)
+                            .sum();
+                        spread += sign * 2 * left;
+                        break;
+                    }
+                }
+                None => {
+                    // Passing draws nothing, so after two passes in a row
+                    // neither player can ever move again.
+                    passes += 1;
+                    if passes == 2 {
+                        break;
+                    }
+                }
+            }
+            player = 1 - player;
+        }
+        spread
+    }
+
+    /// The move the strategy picks for `rack`, `None` to pass.
+    fn choose(&self, board: &Board, rack: &[Letter]) -> Option<LegalMove> {
+        let moves = match self.simulation.strategy {
+            Strategy::Greedy => self.solver.moves_for_tiles(board, rack),
+            Strategy::Equity => self.solver.moves_by_equity_for_tiles(board, rack),
+        };
+        moves.ok()?.into_iter().next()
+    }
+}
@@ -0,0 +1,201 @@
+//! The high-level entry point of the crate.
+
+use crate::board::{Board, Placement};
+use crate::error::Error;
+use crate::leave::{leave_after, Leaves};
+use crate::legal_moves::{
+    calculate_exchanges, calculate_legal_moves, rack_from_tiles, LegalMove, Move,
+};
+use crate::letter::Letter;
+use crate::lexicon::Lexicon;
+use std::cmp::Reverse;
+
+/// Finds and plays moves on a [`Board`] using a given lexicon.
+pub struct Solver {
+    lexicon: Lexicon,
+    leaves: Leaves,
+}
+
+impl Solver {
+    /// Builds a solver. Moves are generated with the lexicon's GADDAG if it
+    /// has one, see [`Lexicon::with_gaddag`], and with its DAWG otherwise.
+    pub fn new(lexicon: Lexicon) -> Solver {
+        Solver {
+            lexicon,
+            leaves: Leaves::heuristic(),
+        }
+    }
+
+    /// Values the leaves of moves with `leaves` rather than the heuristic
+    /// alone.
+    pub fn with_leaves(mut self, leaves: Leaves) -> Solver {
+        self.leaves = leaves;
+        self
+    }
+
+    /// The lexicon moves are checked against.
+    pub fn lexicon(&self) -> &Lexicon {
+        &self.lexicon
+    }
+
+    /// The values of the tiles kept after a move.
+    pub fn leaves(&self) -> &Leaves {
+        &self.leaves
+    }
+
+    /// Fails if the lexicon and the ruleset of `board` do not number the
+    /// letters alike, in which case neither would understand the other.
+    fn check_alphabet(&self, board: &Board) -> Result<(), Error> {
+        if board.ruleset().alphabet != self.lexicon.alphabet {
+            return Err(Error::InvalidLexicon(format!(
+                "The lexicon is not written in the alphabet of the {} ruleset.",
+                board.ruleset().name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Lists every legal move, across and down, for a rack written as
+    /// letters with `?` for a blank, each once, with its equity. The
+    /// highest-scoring moves come first.
+    pub fn moves(&self, board: &Board, rack: &str) -> Result<Vec<LegalMove>, Error> {
+        self.moves_for_tiles(board, &board.ruleset().alphabet.parse(rack)?)
+    }
+
+    /// Lists the moves like [`Solver::moves`] for a rack given as tiles,
+    /// [`BLANK`](crate::letter::BLANK) standing for a blank.
+    pub fn moves_for_tiles(&self, board: &Board, rack: &[Letter]) -> Result<Vec<LegalMove>, Error> {
+        self.check_alphabet(board)?;
+        let mut rack_tiles = rack_from_tiles(rack, &board.ruleset().alphabet)?;
+        let mut legal_moves = calculate_legal_moves(&self.lexicon, board, &mut rack_tiles)?;
+        for legal_move in &mut legal_moves {
+            let leave = leave_after(
+                rack,
+                legal_move.placed_tiles.iter().map(|(_, _, tile)| *tile),
+            );
+            legal_move.equity =
+                legal_move.score as f64 + self.leaves.value(board.ruleset(), &leave);
+        }
+        legal_moves.sort_by_key(|legal_move| Reverse(legal_move.score));
+        Ok(legal_moves)
+    }
+
+    /// Lists the moves like [`Solver::moves`], the highest equity first.
+    pub fn moves_by_equity(&self, board: &Board, rack: &str) -> Result<Vec<LegalMove>, Error> {
+        self.moves_by_equity_for_tiles(board, &board.ruleset().alphabet.parse(rack)?)
+    }
+
+    /// Lists the moves like [`Solver::moves_for_tiles`], the highest equity
+    /// first.
+    pub fn moves_by_equity_for_tiles(
+        &self,
+        board: &Board,
+        rack: &[Letter],
+    ) -> Result<Vec<LegalMove>, Error> {
+        let mut legal_moves = self.moves_for_tiles(board, rack)?;
+        legal_moves.sort_by(|first, second| second.equity.total_cmp(&first.equity));
+        Ok(legal_moves)
+    }
+
+    /// Lists everything the rack can do, the highest equity first: the
+    /// legal moves, every distinct exchange when `in_bag` tiles left in the
+    /// bag allow one, and the pass. An exchange is worth the value of the
+    /// tiles kept, a pass that of the whole rack.
+    ///
+    /// Once the bag is empty no draw will complete the tiles kept: they are
+    /// either played on a later turn or left on the rack when the opponent
+    /// goes out, costing their points to the player and giving as many to
+    /// the opponent. They are then worth minus twice their points rather
+    /// than their usual value, which would otherwise have a player keep a
+    /// blank, worth much as a leave, instead of going out with it.
+    pub fn all_moves(&self, board: &Board, rack: &str, in_bag: usize) -> Result<Vec<Move>, Error> {
+        self.all_moves_for_tiles(board, &board.ruleset().alphabet.parse(rack)?, in_bag)
+    }
+
+    /// Lists everything a rack given as tiles can do, like
+    /// [`Solver::all_moves`].
+    pub fn all_moves_for_tiles(
+        &self,
+        board: &Board,
+        rack_tiles: &[Letter],
+        in_bag: usize,
+    ) -> Result<Vec<Move>, Error> {
+        let ruleset = board.ruleset();
+        let leave_value = |leave: &[Letter]| match in_bag {
+            0 => {
+                -2.0 * leave
+                    .iter()
+                    .map(|tile| ruleset.letter_value(*tile) as f64)
+                    .sum::<f64>()
+            }
+            _ => self.leaves.value(ruleset, leave),
+        };
+        let mut moves: Vec<Move> = self
+            .moves_for_tiles(board, rack_tiles)?
+            .into_iter()
+            .map(|mut legal_move| {
+                let leave = leave_after(
+                    rack_tiles,
+                    legal_move.placed_tiles.iter().map(|(_, _, tile)| *tile),
+                );
+                legal_move.equity = legal_move.score as f64 + leave_value(&leave);
+                Move::Place(legal_move)
+            })
+            .collect();
+        if ruleset.can_exchange(in_bag) {
+            for (thrown, kept) in calculate_exchanges(rack_tiles) {
+                let equity = leave_value(&kept);
+                moves.push(Move::Exchange {
+                    thrown,
+                    kept,
+                    equity,
+                });
+            }
+        }
+        moves.push(Move::Pass {
+            equity: leave_value(&leave_after(rack_tiles, [])),
+        });
+        moves.sort_by(|first, second| second.equity().total_cmp(&first.equity()));
+        Ok(moves)
+    }
+
+    /// Checks a word against the rules of the game, see [`Board::validate_move`].
+    pub fn validate(
+        &self,
+        board: &Board,
+        word: &str,
+        row_index: usize,
+        column_index: usize,
+        across: bool,
+    ) -> Result<Placement, Error> {
+        self.check_alphabet(board)?;
+        board.validate_move(&self.lexicon, word, row_index, column_index, across)
+    }
+
+    /// Lists every word a placement forms, those missing from the lexicon
+    /// included, see [`Board::check_move`].
+    pub fn check(
+        &self,
+        board: &Board,
+        word: &str,
+        row_index: usize,
+        column_index: usize,
+        across: bool,
+    ) -> Result<Placement, Error> {
+        self.check_alphabet(board)?;
+        board.check_move(&self.lexicon, word, row_index, column_index, across)
+    }
+
+    /// Validates a word and plays it, see [`Board::try_play`].
+    pub fn play(
+        &self,
+        board: &mut Board,
+        word: &str,
+        row_index: usize,
+        column_index: usize,
+        across: bool,
+    ) -> Result<Placement, Error> {
+        self.check_alphabet(board)?;
+        board.try_play(&self.lexicon, word, row_index, column_index, across)
+    }
+}
@@ -0,0 +1,224 @@
+//! Self-play: bots playing many games against each other, to compare their
+//! strategies.
+//!
+//! Each game is dealt from a seed of its own, derived from the seed of the
+//! tournament and the number of the game, and the bots take turns to start,
+//! so that results are repeatable whatever the number of threads.
+
+use crate::error::Error;
+use crate::game::Game;
+use crate::legal_moves::Move;
+use crate::random::Random;
+use crate::ruleset::Ruleset;
+use crate::simulation::Simulation;
+use crate::solver::Solver;
+use std::sync::Arc;
+use std::thread;
+
+/// How a bot chooses its moves.
+#[derive(Clone, Debug)]
+pub enum Bot {
+    /// The highest-scoring move, passing when there is none.
+    Greedy,
+    /// The move, exchange or pass with the highest equity.
+    Equity,
+    /// The best candidate by Monte Carlo simulation, falling back on the
+    /// highest equity when no word can be played.
+    Simulation(Simulation),
+}
+
+impl Bot {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Bot::Greedy => "score",
+            Bot::Equity => "equity",
+            Bot::Simulation(_) => "simulation",
+        }
+    }
+
+    /// The move of the player to move of `game`, the `turn`-th of the game
+    /// dealt from `seed`.
+    fn choose(&self, solver: &Solver, game: &Game, seed: u64, turn: usize) -> Result<Move, Error> {
+        let board = game.board();
+        let rack = &game.players()[game.to_move()].rack;
+        let best = match self {
+            Bot::Greedy => solver
+                .moves_for_tiles(board, rack)?
+                .into_iter()
+                .next()
+                .map(Move::Place),
+            Bot::Equity => None,
+            Bot::Simulation(simulation) => {
+                let simulation = Simulation {
+                    seed: Random::for_stream(simulation.seed ^ seed, turn as u64).next_u64(),
+                    ..simulation.clone()
+                };
+                simulation
+                    .run_for_tiles(solver, board, rack)?
+                    .into_iter()
+                    .next()
+                    .map(|simulated| Move::Place(simulated.legal_move))
+            }
+        };
+        Ok(match (self, best) {
+            (_, Some(best)) => best,
+            (Bot::Greedy, None) => Move::Pass { equity: 0.0 },
+            (_, None) => solver
+                .all_moves_for_tiles(board, rack, game.in_bag())?
+                .into_iter()
+                .next()
+                .unwrap_or(Move::Pass { equity: 0.0 }),
+        })
+    }
+}
+
+/// The settings of a tournament.
+#[derive(Clone, Debug)]
+pub struct Tournament {
+    /// The bots sitting at every game, at least one, in turn order for the
+    /// first game.
+    pub bots: Vec<Bot>,
+    pub games: usize,
+    pub seed: u64,
+    pub threads: usize,
+}
+
+impl Default for Tournament {
+    fn default() -> Self {
+        Tournament {
+            bots: vec![Bot::Greedy, Bot::Equity],
+            games: 100,
+            seed: 0,
+            threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+        }
+    }
+}
+
+/// How a game of a tournament went.
+#[derive(Clone, Debug)]
+pub struct GameOutcome {
+    pub seed: u64,
+    /// The index of the bot in each seat, in turn order.
+    pub seats: Vec<usize>,
+    /// The final score of each seat.
+    pub scores: Vec<i32>,
+    /// The number of moves using the whole rack played by each seat.
+    pub bingos: Vec<usize>,
+    /// The game as a GCG record, see [`crate::gcg`].
+    pub gcg: String,
+}
+
+/// The results of a bot over a tournament.
+#[derive(Clone, Debug, Default)]
+pub struct Standing {
+    /// The games won, a tie counting as half a win for each player tied
+    /// for the first place.
+    pub wins: f64,
+    pub games: usize,
+    pub total_score: i64,
+    pub bingos: usize,
+}
+
+impl Standing {
+    pub fn win_rate(&self) -> f64 {
+        self.wins / self.games.max(1) as f64
+    }
+
+    pub fn average_score(&self) -> f64 {
+        self.total_score as f64 / self.games.max(1) as f64
+    }
+
+    pub fn bingos_per_game(&self) -> f64 {
+        self.bingos as f64 / self.games.max(1) as f64
+    }
+}
+
+/// Every game of a tournament and the standing of each bot.
+#[derive(Clone, Debug)]
+pub struct TournamentResult {
+    pub games: Vec<GameOutcome>,
+    /// The standing of each bot, in the order of [`Tournament::bots`].
+    pub standings: Vec<Standing>,
+}
+
+impl Tournament {
+    /// Plays every game of the tournament under `ruleset`.
+    pub fn run(&self, solver: &Solver, ruleset: Arc<Ruleset>) -> Result<TournamentResult, Error> {
+        let threads = self.threads.clamp(1, self.games.max(1));
+        let games: Vec<GameOutcome> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|thread_index| {
+                    let ruleset = &ruleset;
+                    scope.spawn(move || {
+                        (thread_index..self.games)
+                            .step_by(threads)
+                            .map(|index| Ok((index, self.play(solver, ruleset.clone(), index)?)))
+                            .collect::<Result<Vec<_>, Error>>()
+                    })
+                })
+                .collect();
+            let mut games = Vec::new();
+            for worker in workers {
+                games.extend(worker.join().unwrap()?);
+            }
+            games.sort_by_key(|(index, _)| *index);
+            Ok::<_, Error>(games.into_iter().map(|(_, game)| game).collect())
+        })?;
+
+        let mut standings = vec![Standing::default(); self.bots.len()];
+        for game in &games {
+            let best = game.scores.iter().max().copied().unwrap_or(0);
+            let winners = game.scores.iter().filter(|score| **score == best).count();
+            for (seat, bot) in game.seats.iter().enumerate() {
+                let standing = &mut standings[*bot];
+                standing.games += 1;
+                standing.total_score += game.scores[seat] as i64;
+                standing.bingos += game.bingos[seat];
+                if game.scores[seat] == best {
+                    standing.wins += 1.0 / winners as f64;
+                }
+            }
+        }
+        Ok(TournamentResult { games, standings })
+    }
+
+    /// Plays the `index`-th game, the bots sitting from the `index`-th on.
+    fn play(
+        &self,
+        solver: &Solver,
+        ruleset: Arc<Ruleset>,
+        index: usize,
+    ) -> Result<GameOutcome, Error> {
+        let seed = Random::for_stream(self.seed, index as u64).next_u64();
+        let rack_size = ruleset.rack_size;
+        let seats: Vec<usize> = (0..self.bots.len())
+            .map(|seat| (seat + index) % self.bots.len())
+            .collect();
+        let nicknames: Vec<String> = seats
+            .iter()
+            .map(|bot| format!("{}{}", self.bots[*bot].name(), bot + 1))
+            .collect();
+        let mut game = Game::new(ruleset, seats.len(), seed).with_nicknames(&nicknames);
+        let mut bingos = vec![0; seats.len()];
+        let mut turn = 0;
+        while !game.is_over() {
+            let bot = &self.bots[seats[game.to_move()]];
+            let chosen = bot.choose(solver, &game, seed, turn)?;
+            let record = game.apply(solver, &chosen)?;
+            if chosen
+                .placement()
+                .is_some_and(|legal_move| legal_move.placed_tiles.len() == rack_size)
+            {
+                bingos[record.player] += 1;
+            }
+            turn += 1;
+        }
+        Ok(GameOutcome {
+            seed,
+            scores: game.players().iter().map(|player| player.score).collect(),
+            bingos,
+            gcg: game.gcg(),
+            seats,
+        })
+    }
+}
@@ -0,0 +1,48 @@
+//! Checks that tiles written by an alphabet read back as the same tiles,
+//! digraph tiles included.
+
+use scrabble::letter::{Letter, BLANK};
+use scrabble::Alphabet;
+
+#[test]
+fn spanish_tiles_read_back_as_written() {
+    let alphabet = Alphabet::spanish();
+    let l = alphabet.parse("L").unwrap()[0];
+    let c = alphabet.parse("C").unwrap()[0];
+    let h = alphabet.parse("H").unwrap()[0];
+    assert_eq!(alphabet.write(&[l, l]), "[L]L");
+    assert_eq!(alphabet.write(&[c, h, l]), "[C]HL");
+    assert_eq!(alphabet.parse("[L]L").unwrap(), vec![l, l]);
+    assert!(alphabet.parse("[LL").is_err());
+
+    // Every pair of tiles, blanks included, and every three letters.
+    let tiles: Vec<Letter> = alphabet
+        .letters()
+        .flat_map(|letter| [letter, letter | BLANK])
+        .chain([BLANK])
+        .collect();
+    let mut sequences: Vec<Vec<Letter>> = Vec::new();
+    for first in &tiles {
+        for second in &tiles {
+            sequences.push(vec![*first, *second]);
+        }
+    }
+    for first in alphabet.letters() {
+        for second in alphabet.letters() {
+            for third in alphabet.letters() {
+                sequences.push(vec![first, second, third]);
+            }
+        }
+    }
+    for letters in sequences {
+        let written = alphabet.write(&letters);
+        assert_eq!(alphabet.parse(&written).unwrap(), letters, "{written}");
+    }
+}
+
+#[test]
+fn latin_tiles_are_written_without_brackets() {
+    let alphabet = Alphabet::latin();
+    let letters = alphabet.parse("CHeLL?").unwrap();
+    assert_eq!(alphabet.write(&letters), "CHeLL?");
+}
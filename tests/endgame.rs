@@ -0,0 +1,61 @@
+//! Solves endgames small enough to be worked out by hand. Under the French
+//! rules, C is worth 3 points, A, S and T 1, Q 8 and Z 10.
+
+use scrabble::endgame::Endgame;
+use scrabble::{Alphabet, Board, Lexicon, Solver};
+
+/// A board holding CAT across from the centre, with a lexicon in which
+/// only an S extends it.
+fn position() -> (Solver, Board) {
+    let alphabet = Alphabet::latin();
+    let words = ["CAT", "CATS"]
+        .iter()
+        .map(|word| alphabet.parse(word).unwrap())
+        .collect();
+    let mut board = Board::new();
+    board.play("CAT", 7, 7, true).unwrap();
+    (Solver::new(Lexicon::from_words(alphabet, words)), board)
+}
+
+#[test]
+fn going_out_scores_twice_the_other_rack() {
+    let (solver, board) = position();
+    let solution = Endgame::default().solve(&solver, &board, "S", "Z").unwrap();
+    // CATS scores 3 + 1 + 1 + 1 = 6, the S on a plain square, and going
+    // out 2 × 10 for the Z.
+    assert!(solution.exact);
+    assert_eq!(solution.spread, 26);
+    let [Some(cats)] = solution.principal_variation.as_slice() else {
+        panic!("{:?}", solution.principal_variation);
+    };
+    assert_eq!((cats.letters.as_str(), cats.score), ("CATS", 6));
+}
+
+#[test]
+fn two_passes_end_the_game() {
+    let (solver, board) = position();
+    let solution = Endgame::default().solve(&solver, &board, "Q", "Z").unwrap();
+    // Neither player can move: each loses the value of their rack.
+    assert!(solution.exact);
+    assert_eq!(solution.spread, 10 - 8);
+    assert!(matches!(
+        solution.principal_variation.as_slice(),
+        [None, None]
+    ));
+}
+
+#[test]
+fn best_line_is_searched_to_the_end_of_the_game() {
+    let (solver, board) = position();
+    let solution = Endgame::default()
+        .solve(&solver, &board, "QS", "Z")
+        .unwrap();
+    // Playing CATS for 6 and then passing twice leaves the Q against the Z,
+    // 6 + 10 - 8 = 8, where passing at once would give 10 - 9 = 1.
+    assert!(solution.exact);
+    assert_eq!(solution.spread, 8);
+    match solution.principal_variation.as_slice() {
+        [Some(cats), None, None] => assert_eq!(cats.letters, "CATS"),
+        variation => panic!("{variation:?}"),
+    }
+}
@@ -0,0 +1,119 @@
+//! Plays short games to the end, on a small board where every tile is an A
+//! so that the racks do not depend on the shuffle, and on the French rules.
+
+use scrabble::game::SCORELESS_TURNS_LIMIT;
+use scrabble::ruleset::Ruleset;
+use scrabble::{Error, Game, Lexicon, Solver, TurnError};
+use std::sync::Arc;
+
+/// Six A tiles worth a point each, racks of two and a 5x5 board without
+/// premium squares: two racks are dealt and two tiles left in the bag.
+const RULESET: &str = "name Tiny
+rack_size 2
+bingo_bonus 0
+alphabet A
+tile A 6 1
+layout .....
+layout .....
+layout .....
+layout .....
+layout .....
+";
+
+fn tiny_game() -> (Solver, Game) {
+    let ruleset: Ruleset = RULESET.parse().unwrap();
+    let alphabet = ruleset.alphabet.clone();
+    let words = ["AA", "AAA"]
+        .iter()
+        .map(|word| alphabet.parse(word).unwrap())
+        .collect();
+    let solver = Solver::new(Lexicon::from_words(alphabet, words));
+    (solver, Game::new(Arc::new(ruleset), 2, 1))
+}
+
+fn scores(game: &Game) -> Vec<i32> {
+    game.players().iter().map(|player| player.score).collect()
+}
+
+#[test]
+fn going_out_takes_the_value_of_the_other_rack() {
+    let (solver, mut game) = tiny_game();
+    game.play(&solver, "AA", 2, 2, true).unwrap();
+    assert_eq!(game.in_bag(), 0);
+    // The second player keeps one A, which the first cannot exchange.
+    game.play(&solver, "AAA", 2, 2, true).unwrap();
+    assert!(matches!(
+        game.exchange(&game.players()[0].rack.clone()),
+        Err(Error::IllegalTurn(TurnError::ExchangeNotAllowed {
+            in_bag: 0,
+            needed: 2
+        }))
+    ));
+    let record = game.play(&solver, "AAA", 1, 2, false).unwrap();
+    assert_eq!(record.end_adjustments, vec![1, -1]);
+    assert!(game.is_over());
+    assert_eq!(scores(&game), vec![2 + 3 + 1, 3 - 1]);
+    assert!(matches!(
+        game.pass(),
+        Err(Error::IllegalTurn(TurnError::GameOver))
+    ));
+
+    // Taking back the last move puts the tiles back on the rack and the
+    // points back as they were.
+    assert!(game.undo());
+    assert!(!game.is_over());
+    assert_eq!(scores(&game), vec![2, 3]);
+    assert_eq!(game.players()[0].rack.len(), 2);
+    assert_eq!(game.board().tile(1, 2), 0);
+    assert!(game.redo());
+    assert!(game.is_over());
+    assert_eq!(scores(&game), vec![6, 2]);
+    assert!(!game.redo());
+}
+
+#[test]
+fn scoreless_turns_end_the_game() {
+    let (_, mut game) = tiny_game();
+    for _ in 1..SCORELESS_TURNS_LIMIT {
+        game.pass().unwrap();
+    }
+    assert!(!game.is_over());
+    let record = game.pass().unwrap();
+    assert_eq!(record.end_adjustments, vec![-2, -2]);
+    assert!(game.is_over());
+    assert_eq!(scores(&game), vec![-2, -2]);
+}
+
+#[test]
+fn undone_exchange_restores_the_bag_and_racks() {
+    let mut game = Game::new(Arc::new(Ruleset::french()), 2, 7);
+    let rack = game.players()[0].rack.clone();
+    let record = game.exchange(&rack[..3]).unwrap().clone();
+    assert_eq!(record.drawn.len(), 3);
+    assert_eq!(game.in_bag(), 102 - 14);
+    let after = game.players().to_vec();
+
+    assert!(game.undo());
+    assert_eq!(game.players()[0].rack, rack);
+    assert_eq!(game.to_move(), 0);
+    assert_eq!(game.in_bag(), 102 - 14);
+    assert!(game.redo());
+    assert_eq!(game.players(), after.as_slice());
+
+    // The bag and its shuffle come back too: exchanging the same tiles
+    // again draws the same ones.
+    assert!(game.undo());
+    assert_eq!(game.exchange(&rack[..3]).unwrap(), &record);
+    assert_eq!(game.players(), after.as_slice());
+}
+
+#[test]
+fn exchange_of_no_tile_is_refused() {
+    let mut game = Game::new(Arc::new(Ruleset::french()), 2, 7);
+    assert!(matches!(
+        game.exchange(&[]),
+        Err(Error::IllegalTurn(TurnError::NothingToExchange))
+    ));
+    assert_eq!(game.to_move(), 0);
+    assert_eq!(game.in_bag(), 102 - 14);
+}
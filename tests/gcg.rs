@@ -0,0 +1,69 @@
+//! Replays and analyses small game records with a handful of words.
+
+use scrabble::game::ChallengeRule;
+use scrabble::gcg::{Action, Game};
+use scrabble::ruleset::Ruleset;
+use scrabble::{Alphabet, Lexicon, Solver};
+use std::sync::Arc;
+
+const WORDS: &[&str] = &["AIR", "AT", "CAT", "CATS", "IS"];
+
+fn lexicon() -> Lexicon {
+    let alphabet = Alphabet::latin();
+    let words = WORDS
+        .iter()
+        .map(|word| alphabet.parse(word).unwrap())
+        .collect();
+    Lexicon::from_words(alphabet, words)
+}
+
+#[test]
+fn tile_forming_words_both_ways_is_found_whatever_its_direction() {
+    // The S of the last turn forms IS down and CATS across, a move listed
+    // once by the generator as its across reading.
+    let game: Game = "#player1 a A
+#player2 b B
+>a: ACEINRT 8H CAT +10 10
+>b: AEHIRST 7J AIR +5 5
+>a: EEINRST K7 .S +8 18
+"
+    .parse()
+    .unwrap();
+    let solver = Solver::new(lexicon());
+    let positions = game
+        .replay(solver.lexicon(), Arc::new(Ruleset::default()))
+        .unwrap();
+    assert_eq!(positions.last().unwrap().scores, vec![18, 5]);
+    let analysis = game.analyse(&solver, &positions[2], 2).unwrap().unwrap();
+    let played = &analysis.moves[analysis.played.unwrap()];
+    assert_eq!(
+        (played.row_index, played.column_index, played.across),
+        (7, 7, true)
+    );
+    assert_eq!(played.letters, "CATS");
+}
+
+#[test]
+fn withdrawn_play_survives_a_round_trip() {
+    let solver = Solver::new(lexicon());
+    let ruleset = Arc::new(Ruleset::french());
+    let mut game =
+        scrabble::Game::new(ruleset.clone(), 2, 7).with_challenge_rule(ChallengeRule::Single);
+    // The first two tiles of the rack, neither of them a blank, make no word
+    // of the lexicon.
+    let rack = game.players()[0].rack.clone();
+    let phony = ruleset.alphabet.write(&rack[..2]);
+    game.play(&solver, &phony, 7, 7, true).unwrap();
+    assert_eq!(game.challenge(&solver).unwrap(), vec![phony]);
+    game.pass().unwrap();
+
+    let record = game.gcg();
+    let withdrawn = format!(">player1: {} -- -", ruleset.alphabet.write(&rack));
+    assert!(record.contains(&withdrawn), "{record}");
+    let parsed: Game = record.parse().unwrap();
+    assert_eq!(parsed.turns[1].action, Action::Withdrawn);
+    let positions = parsed.replay(solver.lexicon(), ruleset).unwrap();
+    let scores: Vec<i32> = game.players().iter().map(|player| player.score).collect();
+    assert_eq!(positions.last().unwrap().scores, scores);
+    assert!(positions.last().unwrap().board.is_empty());
+}
@@ -0,0 +1,339 @@
+//! Compares the move generator with a brute-force reference on random
+//! positions built with a small lexicon.
+
+use scrabble::legal_moves::{calculate_legal_moves, parse_rack};
+use scrabble::letter::{Blank, Letter, BLANK};
+use scrabble::score::SquareType;
+use scrabble::{Alphabet, Board, Lexicon};
+use std::collections::BTreeSet;
+
+const WORDS: &[&str] = &[
+    "AA", "AB", "AD", "AE", "AG", "AH", "AI", "AL", "AM", "AN", "AR", "AS", "AT", "AW", "AX", "AY",
+    "BA", "BE", "BI", "BO", "BY", "DA", "DE", "DO", "ED", "EF", "EH", "EL", "EM", "EN", "ER", "ES",
+    "EX", "FA", "FE", "GO", "HA", "HE", "HI", "HO", "ID", "IF", "IN", "IS", "IT", "JO", "KA", "KI",
+    "LA", "LI", "LO", "MA", "ME", "MI", "MO", "MU", "MY", "NA", "NE", "NO", "NU", "OD", "OE", "OF",
+    "OH", "OI", "OM", "ON", "OP", "OR", "OS", "OW", "OX", "OY", "PA", "PE", "PI", "QI", "RE", "SH",
+    "SI", "SO", "TA", "TI", "TO", "UH", "UM", "UN", "UP", "US", "UT", "WE", "WO", "XI", "XU", "YA",
+    "YE", "YO", "ZA", "ANT", "ARE", "ART", "ATE", "EAR", "EAT", "ERA", "ETA", "NET", "NIT", "NOR",
+    "NOT", "OAR", "ONE", "ORE", "RAN", "RAT", "ROT", "SAT", "SEA", "SET", "SIT", "TAN", "TAR",
+    "TEA", "TEN", "TIE", "TIN", "TOE", "TON", "ANTE", "ARTS", "EARN", "EAST", "EATS", "IRON",
+    "NEAR", "NEST", "NOTE", "RAIN", "RANT", "RATE", "REST", "RIOT", "ROSE", "SANE", "SEAT", "SENT",
+    "STAR", "TEAR", "TOES", "TONE", "TSAR", "ASTER", "IRATE", "NOTES", "ONSET", "RATES", "RAINS",
+    "SAINT", "SATIN", "SNORE", "STAIN", "STARE", "STONE", "TEARS", "TONES", "TRAIN", "RETAIN",
+    "SENIOR", "STONER", "TRAINS", "RETAINS", "STAINER", "NOTARIES",
+];
+
+/// The tiles racks are drawn from, with roughly the English frequencies.
+const TILES: &str =
+    "AAAAAAAAABBCCDDDDEEEEEEEEEEEEFFGGGHHIIIIIIIIIJKLLLLMMNNNNNNOOOOOOOOPPQRRRRRRSSSSTTTTTTUUUUVVWWXYYZ??";
+
+/// A move as `(row_index, column_index, across, word, score)`.
+type Move = (usize, usize, bool, String, u16);
+
+/// The tiles a move places, as `(row_index, column_index, tile)`.
+type PlacedTiles = Vec<(usize, usize, Letter)>;
+
+/// A xorshift generator, so that failures can be replayed from their seed.
+struct Random(u64);
+
+impl Random {
+    fn below(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % bound as u64) as usize
+    }
+}
+
+fn lexicon() -> Lexicon {
+    let alphabet = Alphabet::latin();
+    let words = WORDS
+        .iter()
+        .map(|word| alphabet.parse(word).unwrap())
+        .collect();
+    Lexicon::from_words(alphabet, words)
+}
+
+fn random_rack(random: &mut Random) -> String {
+    let tiles: Vec<char> = TILES.chars().collect();
+    (0..7).map(|_| tiles[random.below(tiles.len())]).collect()
+}
+
+/// The squares of the word through a square in one direction, given the
+/// tile on each square.
+fn word_squares(
+    size: usize,
+    tile: impl Fn(usize, usize) -> Letter,
+    (row, column): (usize, usize),
+    across: bool,
+) -> Vec<(usize, usize)> {
+    let step = |(row, column): (usize, usize), forward: bool| -> Option<(usize, usize)> {
+        let (row, column) = match (across, forward) {
+            (true, true) => (Some(row), column.checked_add(1)),
+            (true, false) => (Some(row), column.checked_sub(1)),
+            (false, true) => (row.checked_add(1), Some(column)),
+            (false, false) => (row.checked_sub(1), Some(column)),
+        };
+        let square = (row?, column?);
+        (square.0 < size && square.1 < size && tile(square.0, square.1) != 0).then_some(square)
+    };
+    let mut start = (row, column);
+    while let Some(square) = step(start, false) {
+        start = square;
+    }
+    let mut squares = vec![start];
+    while let Some(square) = step(*squares.last().unwrap(), true) {
+        squares.push(square);
+    }
+    squares
+}
+
+/// Scores a placement from the rules alone, without the scoring of the
+/// crate: every word of two tiles or more it forms is worth the values of
+/// its tiles, letter premiums counting on new squares only, times the word
+/// premiums of its new squares, and playing a whole rack adds the bingo
+/// bonus.
+fn reference_score(board: &Board, placed_tiles: &PlacedTiles, across: bool) -> u16 {
+    let ruleset = board.ruleset();
+    let placed = |row: usize, column: usize| {
+        placed_tiles
+            .iter()
+            .find(|(placed_row, placed_column, _)| (*placed_row, *placed_column) == (row, column))
+            .map(|(_, _, tile)| *tile)
+    };
+    let tile = |row: usize, column: usize| placed(row, column).unwrap_or(board.tile(row, column));
+    let word_score = |squares: Vec<(usize, usize)>| -> u16 {
+        if squares.len() < 2 {
+            return 0;
+        }
+        let mut sum = 0;
+        let mut word_multiplier = 1;
+        for (row, column) in squares {
+            let (letter_multiplier, multiplier) = match placed(row, column) {
+                None => (1, 1),
+                Some(_) => match ruleset.premium_squares[(row, column)] {
+                    SquareType::Normal => (1, 1),
+                    SquareType::DoubleLetter => (2, 1),
+                    SquareType::TripleLetter => (3, 1),
+                    SquareType::QuadrupleLetter => (4, 1),
+                    SquareType::DoubleWord => (1, 2),
+                    SquareType::TripleWord => (1, 3),
+                    SquareType::QuadrupleWord => (1, 4),
+                },
+            };
+            sum += ruleset.letter_value(tile(row, column)) * letter_multiplier;
+            word_multiplier *= multiplier;
+        }
+        sum * word_multiplier
+    };
+    let (first_row, first_column, _) = placed_tiles[0];
+    let mut score = word_score(word_squares(
+        board.size(),
+        tile,
+        (first_row, first_column),
+        across,
+    ));
+    for (row, column, _) in placed_tiles {
+        score += word_score(word_squares(board.size(), tile, (*row, *column), !across));
+    }
+    if placed_tiles.len() == ruleset.rack_size {
+        score += ruleset.bingo_bonus;
+    }
+    score
+}
+
+/// Every move found by trying each word of the lexicon on each square in
+/// both directions, with every way of using the blanks of the rack. A lone
+/// tile forming words both ways is listed once for each. Moves are scored
+/// by [`reference_score`].
+fn brute_force_moves(
+    board: &Board,
+    lexicon: &Lexicon,
+    rack: &str,
+) -> BTreeSet<(PlacedTiles, Move)> {
+    let alphabet = &board.ruleset().alphabet;
+    let rack_tiles = alphabet.parse(rack).unwrap();
+    let blank_count = rack_tiles.iter().filter(|tile| **tile == BLANK).count();
+    let size = board.size();
+    let mut moves = BTreeSet::new();
+    for word in WORDS {
+        let letters = alphabet.parse(word).unwrap();
+        for across in [true, false] {
+            for row_index in 0..size {
+                for column_index in 0..size {
+                    let square = |index: usize| {
+                        if across {
+                            (row_index, column_index + index)
+                        } else {
+                            (row_index + index, column_index)
+                        }
+                    };
+                    let (end_row, end_column) = square(letters.len() - 1);
+                    if end_row >= size || end_column >= size {
+                        continue;
+                    }
+                    // Only whole words count: a word that another tile
+                    // extends is found as the longer word.
+                    let (before_row, before_column) = (row_index as isize, column_index as isize);
+                    let before = if across {
+                        (before_row, before_column - 1)
+                    } else {
+                        (before_row - 1, before_column)
+                    };
+                    let after = if across {
+                        (end_row, end_column + 1)
+                    } else {
+                        (end_row + 1, end_column)
+                    };
+                    if (before.0 >= 0
+                        && before.1 >= 0
+                        && board.tile(before.0 as usize, before.1 as usize) != 0)
+                        || (after.0 < size && after.1 < size && board.tile(after.0, after.1) != 0)
+                    {
+                        continue;
+                    }
+                    let mut new_indices = Vec::new();
+                    let mut written = Vec::new();
+                    let mut fits = true;
+                    for (index, letter) in letters.iter().enumerate() {
+                        let (row, column) = square(index);
+                        match board.tile(row, column) {
+                            0 => {
+                                new_indices.push(index);
+                                written.push(*letter);
+                            }
+                            tile if tile.unblanked() == *letter => written.push(tile),
+                            _ => fits = false,
+                        }
+                    }
+                    if !fits || new_indices.is_empty() {
+                        continue;
+                    }
+                    // Placements touching neither a tile nor the centre
+                    // square are illegal: skip them before validating.
+                    let touches = new_indices.iter().any(|&index| {
+                        let (row, column) = square(index);
+                        (row, column) == board.centre()
+                            || (row > 0 && board.tile(row - 1, column) != 0)
+                            || (row + 1 < size && board.tile(row + 1, column) != 0)
+                            || (column > 0 && board.tile(row, column - 1) != 0)
+                            || (column + 1 < size && board.tile(row, column + 1) != 0)
+                    });
+                    if !touches {
+                        continue;
+                    }
+                    // Try every set of new tiles played with a blank.
+                    for mask in 0u32..1 << new_indices.len() {
+                        if mask.count_ones() as usize > blank_count {
+                            continue;
+                        }
+                        let mut available = rack_tiles.clone();
+                        let mut candidate = written.clone();
+                        let mut playable = true;
+                        for (bit, &index) in new_indices.iter().enumerate() {
+                            let tile = if mask & 1 << bit != 0 {
+                                candidate[index] |= BLANK;
+                                BLANK
+                            } else {
+                                candidate[index]
+                            };
+                            match available.iter().position(|rack_tile| *rack_tile == tile) {
+                                Some(position) => {
+                                    available.remove(position);
+                                }
+                                None => playable = false,
+                            }
+                        }
+                        if !playable {
+                            continue;
+                        }
+                        let text = alphabet.write(&candidate);
+                        if board
+                            .validate_move(lexicon, &text, row_index, column_index, across)
+                            .is_ok()
+                        {
+                            let placed_tiles: PlacedTiles = new_indices
+                                .iter()
+                                .map(|&index| {
+                                    let (row, column) = square(index);
+                                    (row, column, candidate[index])
+                                })
+                                .collect();
+                            let score = reference_score(board, &placed_tiles, across);
+                            moves.insert((
+                                placed_tiles,
+                                (row_index, column_index, across, text, score),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    moves
+}
+
+fn check_positions(lexicon: &Lexicon, seed: u64) {
+    let mut random = Random(seed);
+    for position in 0..6 {
+        let mut board = Board::new();
+        let move_count = random.below(8);
+        for _ in 0..move_count {
+            let rack = random_rack(&mut random);
+            let moves: Vec<_> = brute_force_moves(&board, lexicon, &rack)
+                .into_iter()
+                .collect();
+            if moves.is_empty() {
+                continue;
+            }
+            let (_, (row_index, column_index, across, word, _)) = &moves[random.below(moves.len())];
+            board
+                .play(word, *row_index, *column_index, *across)
+                .unwrap();
+        }
+        for _ in 0..3 {
+            let rack = random_rack(&mut random);
+            let context = format!("seed {seed}, position {position}, rack {rack}\n{board}");
+            let expected = brute_force_moves(&board, lexicon, &rack);
+            let mut rack_tiles = parse_rack(&rack, &board.ruleset().alphabet).unwrap();
+            let mut found = BTreeSet::new();
+            for legal_move in calculate_legal_moves(lexicon, &board, &mut rack_tiles).unwrap() {
+                let key = legal_move.placed_tiles.clone();
+                let reading = (
+                    legal_move.row_index,
+                    legal_move.column_index,
+                    legal_move.across,
+                    legal_move.letters,
+                    legal_move.score,
+                );
+                assert!(
+                    expected.contains(&(key.clone(), reading.clone())),
+                    "{context}\nunexpected: {reading:?}"
+                );
+                assert!(found.insert(key), "{context}\nfound twice: {reading:?}");
+            }
+            let missing: Vec<_> = expected
+                .iter()
+                .filter(|(key, _)| !found.contains(key))
+                .map(|(_, reading)| reading)
+                .collect();
+            assert!(missing.is_empty(), "{context}\nmissing: {missing:?}");
+        }
+    }
+}
+
+#[test]
+fn dawg_generator_matches_brute_force() {
+    let lexicon = lexicon();
+    for seed in 1..=3 {
+        check_positions(&lexicon, seed * 0x9E37_79B9);
+    }
+}
+
+#[test]
+fn gaddag_generator_matches_brute_force() {
+    let lexicon = lexicon().with_gaddag();
+    for seed in 1..=3 {
+        check_positions(&lexicon, seed * 0x85EB_CA6B);
+    }
+}